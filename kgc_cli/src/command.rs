@@ -0,0 +1,159 @@
+pub mod batch;
+pub mod diff;
+pub mod facilities;
+pub mod fetch;
+pub mod init;
+pub mod next;
+pub mod remind;
+pub mod serve;
+pub mod sync;
+pub mod watch;
+
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{Duration, Local};
+use clap::Args;
+use kgc_core::{
+    address::Address,
+    archive::Archive,
+    garbage_client::WasteData,
+    official_calendar,
+    official_calendar::MergeStrategy,
+    prediction, provider,
+    provider::{Provider, DEFAULT_PROVIDER},
+    source::{ScheduleSource, SourceConfig, SourceKind},
+};
+
+use crate::{config::Config, GlobalArguments};
+
+/// The number of days from today until which the recurring rules of the official calendar are expanded.
+static OFFICIAL_CALENDAR_DAYS: i64 = 365;
+
+/// The options which select an address and the sources of its schedule.
+#[derive(Debug, Clone, Args)]
+pub struct ScheduleArguments {
+    /// the street, or the name of a profile of the configuration file
+    pub street: Option<String>,
+    /// the street number, which is omitted for a profile
+    pub street_number: Option<String>,
+    /// the district, which matches the stops of the mobile hazardous waste collection
+    #[arg(long)]
+    pub district: Option<String>,
+    /// the municipality whose official website is read
+    #[arg(long, default_value_t = String::from(DEFAULT_PROVIDER))]
+    pub provider: String,
+    /// the number of days past collection dates of the archive are kept
+    #[arg(long, default_value_t = 365)]
+    pub look_back_days: i64,
+    /// predict collection dates after the published ones for this number of days from today
+    #[arg(long)]
+    pub predict_days: Option<i64>,
+    /// the URL or the path of the official iCalendar download to reconcile the scraped dates with
+    #[arg(long)]
+    pub official_calendar: Option<String>,
+    /// the strategy to merge the scraped dates with the official ones (prefer-scraped, scraped or official)
+    #[arg(long, default_value_t = MergeStrategy::PreferScraped)]
+    pub merge_strategy: MergeStrategy,
+    /// read a saved HTML response of the official website instead of fetching it
+    #[arg(long)]
+    pub from_html: Option<PathBuf>,
+    /// the sources which are tried in order (website, html, official, static or archive)
+    #[arg(long, value_delimiter = ',')]
+    pub source: Vec<SourceKind>,
+    /// the JSON file of the static dataset source
+    #[arg(long)]
+    pub static_dataset: Option<PathBuf>,
+}
+
+impl ScheduleArguments {
+    /// Get the name of the profile if a single value is given instead of the street and the street number.
+    pub fn profile(&self) -> Option<&str> {
+        match (&self.street, &self.street_number) {
+            (Some(name), None) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Replace a profile with its options and its locale.
+    pub fn resolve(
+        &self,
+        global_arguments: &GlobalArguments,
+    ) -> Result<(ScheduleArguments, GlobalArguments)> {
+        match self.profile() {
+            Some(name) => {
+                let config = Config::load(global_arguments)?;
+                let profile = config.profile(name)?;
+                Ok((
+                    profile.schedule_arguments()?,
+                    profile.global_arguments(global_arguments),
+                ))
+            }
+            None => Ok((self.clone(), global_arguments.clone())),
+        }
+    }
+
+    /// Get the selected provider.
+    pub fn provider(&self, global_arguments: &GlobalArguments) -> Result<Arc<dyn Provider>> {
+        provider::provider(&self.provider, global_arguments.url.as_deref())
+            .ok_or_else(|| anyhow!("the provider \"{}\" is not supported", self.provider))
+    }
+
+    /// Get the provider, the resolved address and its waste data from the sources.
+    ///
+    /// The warnings of the sources and the disagreements with the official iCalendar download are printed to stderr.
+    pub async fn fetch(
+        &self,
+        global_arguments: &GlobalArguments,
+    ) -> Result<(Arc<dyn Provider>, Address, WasteData)> {
+        let provider = self.provider(global_arguments)?;
+        let (Some(street), Some(street_number)) = (&self.street, &self.street_number) else {
+            bail!("the street and the street number, or the name of a profile, are required");
+        };
+        let address = Address::new(street, street_number)?.with_district(self.district.as_deref());
+        let source_kinds = match (self.source.is_empty(), &self.from_html) {
+            (true, Some(_)) => vec![SourceKind::Html],
+            (true, None) => vec![SourceKind::Website],
+            (false, _) => self.source.clone(),
+        };
+        let source_config = SourceConfig {
+            html_file: self.from_html.clone(),
+            official_calendar: self.official_calendar.clone(),
+            official_calendar_days: OFFICIAL_CALENDAR_DAYS,
+            static_dataset: self.static_dataset.clone(),
+            archive: global_arguments
+                .cache
+                .as_ref()
+                .map(|directory| Arc::new(Archive::new(directory, self.look_back_days))),
+        };
+        if source_config.archive.is_none() && source_kinds.contains(&SourceKind::Archive) {
+            bail!("the source \"archive\" requires the option --cache");
+        }
+        let (address, mut waste_data) = source_config
+            .chain(provider.clone(), &source_kinds)?
+            .fetch(&address)
+            .await?;
+        for warning in waste_data.warnings.drain(..) {
+            eprintln!("Warning: {warning}");
+        }
+        if let Some(location) = &self.official_calendar {
+            let official_waste_data = official_calendar::get(
+                location,
+                Local::now().date_naive() + Duration::days(OFFICIAL_CALENDAR_DAYS),
+            )
+            .await?;
+            for disagreement in official_calendar::reconcile(&waste_data, &official_waste_data) {
+                eprintln!("{}", disagreement.describe(global_arguments.locale));
+            }
+            waste_data =
+                official_calendar::merge(waste_data, official_waste_data, self.merge_strategy);
+        }
+        if let Some(predict_days) = self.predict_days {
+            prediction::predict(
+                &mut waste_data,
+                Local::now().date_naive() + Duration::days(predict_days),
+            );
+        }
+        Ok((provider, address, waste_data))
+    }
+}
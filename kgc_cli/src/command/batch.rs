@@ -0,0 +1,261 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use kgc_core::{garbage_client::WasteType, provider::DEFAULT_PROVIDER};
+use serde::Deserialize;
+use tokio::{
+    sync::Semaphore,
+    task::JoinSet,
+    time::{interval, MissedTickBehavior},
+};
+
+use crate::{
+    command::fetch::{write_file, Target},
+    config::Profile,
+    GlobalArguments,
+};
+
+#[derive(Debug, Args)]
+pub struct Arguments {
+    /// the CSV or JSON file of the addresses, whose columns are street, street_number, district, exclude and output
+    pub file: PathBuf,
+    /// the directory to which relative outputs are written
+    #[arg(long, default_value = ".")]
+    pub output_directory: PathBuf,
+    /// the maximum number of addresses which are fetched at the same time
+    #[arg(long, default_value_t = 4)]
+    pub concurrency: usize,
+    /// the minimum number of milliseconds between the starts of two fetches, which spares the official website
+    #[arg(long, default_value_t = 500)]
+    pub delay_ms: u64,
+    /// the municipality whose official website is read
+    #[arg(long, default_value_t = String::from(DEFAULT_PROVIDER))]
+    pub provider: String,
+    /// add separate events for collections which are shifted from the regular weekday, e.g. because of a holiday
+    #[arg(long)]
+    pub mark_shifted: bool,
+}
+
+/// An address of the file, e.g. a building.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Row {
+    pub street: String,
+    pub street_number: String,
+    #[serde(default)]
+    pub district: Option<String>,
+    /// The excluded waste types, a list or a string separated by ";" or spaces, e.g. "bulky;hazardous".
+    #[serde(default)]
+    pub exclude: Exclude,
+    /// The path of the calendar file, `<street>_<street_number>.ics` by default.
+    #[serde(default)]
+    pub output: Option<PathBuf>,
+}
+
+/// The excluded waste types of a row, which are a list in JSON and a string in CSV.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Exclude {
+    List(Vec<WasteType>),
+    Text(String),
+}
+
+impl Default for Exclude {
+    fn default() -> Self {
+        Exclude::List(Vec::new())
+    }
+}
+
+impl Exclude {
+    /// Get the excluded waste types.
+    pub fn waste_types(&self) -> Result<Vec<WasteType>> {
+        match self {
+            Exclude::List(waste_types) => Ok(waste_types.clone()),
+            Exclude::Text(text) => text
+                .split(|char: char| char == ';' || char.is_whitespace())
+                .filter(|waste_type| !waste_type.is_empty())
+                .map(|waste_type| waste_type.parse().map_err(anyhow::Error::msg))
+                .collect(),
+        }
+    }
+}
+
+impl Row {
+    /// Get the calendar file of this row.
+    fn target(&self, args: &Arguments, global_arguments: &GlobalArguments) -> Result<Target> {
+        let profile = Profile {
+            street: self.street.clone(),
+            street_number: self.street_number.clone(),
+            district: self.district.clone(),
+            provider: Some(args.provider.clone()),
+            exclude: self.exclude.waste_types()?,
+            mark_shifted: args.mark_shifted,
+            ..Profile::default()
+        };
+        let output = self.output.clone().unwrap_or_else(|| {
+            let name: String = format!("{}_{}", self.street, self.street_number)
+                .chars()
+                .map(|char| if char.is_alphanumeric() { char } else { '_' })
+                .collect();
+            PathBuf::from(format!("{name}.ics"))
+        });
+        Ok(Target {
+            profile: None,
+            arguments: profile.fetch_arguments()?,
+            global_arguments: global_arguments.clone(),
+            path: args.output_directory.join(output),
+        })
+    }
+}
+
+/// Read the rows of a JSON file, if its extension is `.json`, or of a CSV file with a header row.
+pub fn read_rows(path: &Path) -> Result<Vec<Row>> {
+    let file =
+        File::open(path).with_context(|| format!("the file {} cannot be read", path.display()))?;
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        return Ok(serde_json::from_reader(file)?);
+    }
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(file)
+        .deserialize()
+        .enumerate()
+        .map(|(index, row)| row.with_context(|| format!("row {} is invalid", index + 1)))
+        .collect()
+}
+
+/// Write the calendars of all addresses of a file and print a report of the failed ones.
+pub async fn run(args: Arguments, global_arguments: &GlobalArguments) -> Result<()> {
+    if args.concurrency == 0 {
+        bail!("the concurrency must be at least 1");
+    }
+    let rows = read_rows(&args.file)?;
+    let semaphore = Arc::new(Semaphore::new(args.concurrency));
+    let mut delay = interval(Duration::from_millis(args.delay_ms.max(1)));
+    delay.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut tasks = JoinSet::new();
+    let mut failed: Vec<(usize, String)> = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        let target = match row.target(&args, global_arguments) {
+            Ok(target) => target,
+            Err(error) => {
+                failed.push((index, format!("{error:#}")));
+                continue;
+            }
+        };
+        let permit = semaphore.clone().acquire_owned().await?;
+        delay.tick().await;
+        tasks.spawn(async move {
+            let result = async {
+                for (path, calendar) in target.files().await? {
+                    write_file(&path, calendar.as_bytes())?;
+                }
+                anyhow::Ok(())
+            }
+            .await;
+            drop(permit);
+            (index, result)
+        });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        let (index, result) = joined?;
+        if let Err(error) = result {
+            failed.push((index, format!("{error:#}")));
+        }
+    }
+    failed.sort();
+    println!(
+        "{} of {} calendars have been written.",
+        rows.len() - failed.len(),
+        rows.len()
+    );
+    for (index, error) in &failed {
+        let row = &rows[*index];
+        println!(
+            "row {}, {} {}: {error}",
+            index + 1,
+            row.street,
+            row.street_number
+        );
+    }
+    if !failed.is_empty() {
+        bail!("the calendars of {} rows cannot be written", failed.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use kgc_core::garbage_client::WasteType;
+
+    use crate::command::batch::{read_rows, Exclude};
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/command/batch/tests")
+            .join(name)
+    }
+
+    /// Test whether the rows of a CSV file are read with trimmed values and excluded waste types separated by ";" or spaces.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_read_rows_csv() {
+        let rows = read_rows(&fixture("rows.csv")).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].street, "Kaiserstraße");
+        assert_eq!(rows[0].street_number, "1");
+        assert_eq!(rows[0].district, None);
+        assert_eq!(rows[0].output, None);
+        assert!(rows[0].exclude.waste_types().unwrap().is_empty());
+        assert_eq!(rows[1].district.as_deref(), Some("Durlach"));
+        assert_eq!(
+            rows[1].exclude.waste_types().unwrap(),
+            vec![WasteType::Bulky, WasteType::Hazardous]
+        );
+        assert_eq!(rows[1].output, Some(PathBuf::from("durlach/pfinztal.ics")));
+        assert_eq!(
+            rows[2].exclude.waste_types().unwrap(),
+            vec![WasteType::Paper, WasteType::Hazardous]
+        );
+    }
+
+    /// Test whether the rows of a JSON file are read with excluded waste types as a list.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_read_rows_json() {
+        let rows = read_rows(&fixture("rows.json")).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].street, "Kaiserstraße");
+        assert!(rows[0].exclude.waste_types().unwrap().is_empty());
+        assert_eq!(rows[1].street_number, "12a");
+        assert_eq!(
+            rows[1].exclude.waste_types().unwrap(),
+            vec![WasteType::Bulky, WasteType::Hazardous]
+        );
+        assert_eq!(rows[1].output, Some(PathBuf::from("durlach/pfinztal.ics")));
+    }
+
+    /// Test whether unknown waste types and missing files are errors.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_read_rows_errors() {
+        assert!(Exclude::Text(String::from("bulky;glass"))
+            .waste_types()
+            .is_err());
+        assert!(read_rows(&fixture("missing.csv")).is_err());
+    }
+}
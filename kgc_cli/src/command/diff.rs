@@ -0,0 +1,49 @@
+use std::{fs::read_to_string, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::{Args, ValueEnum};
+use kgc_core::{
+    diff::diff,
+    garbage_client::parse_calendar,
+    provider::{self, DEFAULT_PROVIDER},
+};
+
+use crate::GlobalArguments;
+
+#[derive(Debug, Args)]
+pub struct Arguments {
+    /// the older calendar file
+    pub old: PathBuf,
+    /// the newer calendar file
+    pub new: PathBuf,
+    /// the output format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+    /// the municipality whose provider generated the calendar files
+    #[arg(long, default_value_t = String::from(DEFAULT_PROVIDER))]
+    pub provider: String,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+/// Compare two calendar files generated by this application and print the changes.
+pub fn run(args: Arguments, global_arguments: &GlobalArguments) -> Result<()> {
+    let provider = provider::provider(&args.provider, global_arguments.url.as_deref())
+        .ok_or_else(|| anyhow!("the provider \"{}\" is not supported", args.provider))?;
+    let old = parse_calendar(&read_to_string(&args.old)?, provider.as_ref())?;
+    let new = parse_calendar(&read_to_string(&args.new)?, provider.as_ref())?;
+    let changes = diff(&old, &new);
+    match args.format {
+        Format::Text => {
+            for change in changes {
+                println!("{}", change.describe(global_arguments.locale));
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&changes)?),
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use std::{env::current_dir, fs::write};
+
+use anyhow::Result;
+use chrono::{Duration, Local};
+use clap::Args;
+use kgc_core::{
+    facility::{get_exceptions_calendar, Dataset, Facility, Material},
+    ical::generator::Emitter,
+};
+
+#[derive(Debug, Args)]
+pub struct Arguments {
+    /// the street, the facilities are ordered by their distance to it
+    #[arg(long)]
+    pub street: Option<String>,
+    /// the district, the facilities are ordered by their distance to it
+    #[arg(long)]
+    pub district: Option<String>,
+    /// only list facilities which accept this material (e.g. green-waste)
+    #[arg(long)]
+    pub material: Option<Material>,
+    /// only list facilities which are open now
+    #[arg(long)]
+    pub open_now: bool,
+    /// write the days on which the listed facilities are closed on a holiday to the file `facilities.ics` for this number of days from today
+    #[arg(long)]
+    pub calendar: Option<i64>,
+    /// print the facilities as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// List the drop-off facilities of the bundled dataset.
+pub fn run(args: Arguments) -> Result<()> {
+    let dataset = Dataset::bundled();
+    let now = Local::now().naive_local();
+    let facilities: Vec<&Facility> = dataset
+        .nearest(args.street.as_deref(), args.district.as_deref())
+        .into_iter()
+        .filter(|facility| {
+            args.material
+                .map_or(true, |material| facility.accepts(material))
+        })
+        .filter(|facility| !args.open_now || dataset.is_open(facility, now))
+        .collect();
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&facilities)?);
+    } else {
+        for facility in &facilities {
+            println!("{facility}");
+        }
+    }
+    if let Some(days) = args.calendar {
+        let today = now.date();
+        let exceptions = dataset.exceptions(&facilities, today, today + Duration::days(days));
+        let mut path = current_dir()?;
+        path.push("facilities.ics");
+        write(path, get_exceptions_calendar(&exceptions).generate())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,390 @@
+use std::{
+    env::current_dir,
+    fs::read_to_string,
+    io::{stdout, Cursor, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, NaiveTime};
+use clap::Args;
+use kgc_core::{
+    address::Address,
+    file::write_atomically,
+    garbage_client,
+    garbage_client::{WasteType, WasteTypeBitmask},
+    ical::generator::{Emitter, IcalCalendar},
+    provider::Provider,
+    render::{
+        parse_time_of_day, Alarm, Assignee, Blackout, BlackoutMode, RenderOptions, Rotation,
+        RotationMode,
+    },
+};
+
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::{
+    command::ScheduleArguments,
+    config::{Config, Profile},
+    GlobalArguments,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct Arguments {
+    #[command(flatten)]
+    pub schedule: ScheduleArguments,
+    /// write the calendars of all profiles of the configuration file
+    #[arg(long, conflicts_with_all = ["street", "output"])]
+    pub all: bool,
+    /// the path of the calendar file, or - for stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+    /// write a calendar file per waste type like the server's paths of the waste types, e.g. `calendar-paper.ics`
+    #[arg(long)]
+    pub split_by_type: bool,
+    /// write the calendar files into this ZIP file instead
+    #[arg(long)]
+    pub bundle: Option<PathBuf>,
+    /// merge the events into this calendar file instead, replacing only the former events of the address
+    #[arg(long, conflicts_with_all = ["output", "split_by_type", "bundle"])]
+    pub merge_into: Option<PathBuf>,
+    /// exclude residual waste collection dates
+    #[arg(long)]
+    pub exclude_residual: bool,
+    /// exclude organic waste collection dates
+    #[arg(long)]
+    pub exclude_organic: bool,
+    /// exclude recyclable waste collection dates
+    #[arg(long)]
+    pub exclude_recyclable: bool,
+    /// exclude paper waste collection dates
+    #[arg(long)]
+    pub exclude_paper: bool,
+    /// exclude bulky waste collection dates
+    #[arg(long)]
+    pub exclude_bulky: bool,
+    /// exclude hazardous waste collection dates
+    #[arg(long)]
+    pub exclude_hazardous: bool,
+    /// add separate events for collections which are shifted from the regular weekday, e.g. because of a holiday
+    #[arg(long)]
+    pub mark_shifted: bool,
+    /// omit collection dates before this date (e.g. 2024-08-01)
+    #[arg(long)]
+    pub from: Option<NaiveDate>,
+    /// omit collection dates after this date (e.g. 2024-08-31)
+    #[arg(long)]
+    pub to: Option<NaiveDate>,
+    /// omit collection dates after this number of days from today
+    #[arg(long)]
+    pub horizon_days: Option<i64>,
+    /// the date ranges without collection alerts, e.g. a vacation (e.g. 2024-08-01..2024-08-21)
+    #[arg(long, value_delimiter = ',')]
+    pub skip: Vec<Blackout>,
+    /// whether collection dates in the skipped date ranges are dropped or marked (drop or mark)
+    #[arg(long, default_value_t = BlackoutMode::Drop)]
+    pub skip_mode: BlackoutMode,
+    /// the household members who take the bins out in turn (e.g. "Anna,Ben <ben@example.org>")
+    #[arg(long, value_delimiter = ',')]
+    pub rotation: Vec<Assignee>,
+    /// whether the rotation advances per collection or per week (collection or week)
+    #[arg(long, default_value_t = RotationMode::Collection)]
+    pub rotation_mode: RotationMode,
+    /// a date in the week in which the first household member of the rotation starts
+    #[arg(long)]
+    pub rotation_start: Option<NaiveDate>,
+    /// the reminders before every collection (e.g. 1d,6h), before an all-day event 6h is at 18:00 on the day before
+    #[arg(long, value_delimiter = ',')]
+    pub alarm: Vec<Alarm>,
+    /// the time at which the bins have to be out (e.g. 06:00), which turns the collections into events at that time
+    #[arg(long, value_parser = parse_time_of_day)]
+    pub time_of_day: Option<NaiveTime>,
+}
+
+impl From<&Arguments> for RenderOptions {
+    fn from(value: &Arguments) -> Self {
+        RenderOptions {
+            mark_shifted: value.mark_shifted,
+            from: value.from,
+            to: value.to,
+            horizon_days: value.horizon_days,
+            blackouts: value.skip.clone(),
+            blackout_mode: value.skip_mode,
+            rotation: (!value.rotation.is_empty()).then(|| Rotation {
+                assignees: value.rotation.clone(),
+                mode: value.rotation_mode,
+                start: value.rotation_start,
+            }),
+            alarms: value.alarm.clone(),
+            time_of_day: value.time_of_day,
+        }
+    }
+}
+
+impl From<&Arguments> for WasteTypeBitmask {
+    fn from(value: &Arguments) -> Self {
+        let mut waste_type_bitmask = WasteTypeBitmask::none();
+        if value.exclude_residual {
+            waste_type_bitmask |= WasteTypeBitmask::Residual;
+        }
+        if value.exclude_organic {
+            waste_type_bitmask |= WasteTypeBitmask::Organic;
+        }
+        if value.exclude_recyclable {
+            waste_type_bitmask |= WasteTypeBitmask::Recyclable;
+        }
+        if value.exclude_paper {
+            waste_type_bitmask |= WasteTypeBitmask::Paper;
+        }
+        if value.exclude_bulky {
+            waste_type_bitmask |= WasteTypeBitmask::Bulky;
+        }
+        if value.exclude_hazardous {
+            waste_type_bitmask |= WasteTypeBitmask::Hazardous;
+        }
+        waste_type_bitmask
+    }
+}
+
+/// The path which writes to stdout instead of a file.
+static STDOUT: &str = "-";
+
+/// A calendar file which is written, e.g. the one of a profile.
+#[derive(Debug, Clone)]
+pub struct Target {
+    /// The name of the profile, if the calendar is written for one.
+    pub profile: Option<String>,
+    pub arguments: Arguments,
+    pub global_arguments: GlobalArguments,
+    pub path: PathBuf,
+}
+
+impl Target {
+    /// Get the calendar of this target with the provider and the address of its events.
+    pub async fn calendar(&self) -> Result<(Arc<dyn Provider>, Address, IcalCalendar)> {
+        let (provider, address, waste_data) = self
+            .arguments
+            .schedule
+            .fetch(&self.global_arguments)
+            .await?;
+        let calendar = garbage_client::get_calendar(
+            provider.as_ref(),
+            &address.requested.0,
+            &address.requested.1,
+            waste_data,
+            WasteTypeBitmask::from(&self.arguments),
+            &RenderOptions::from(&self.arguments),
+        );
+        Ok((provider, address, calendar))
+    }
+
+    /// Get the calendar of this target as iCalendar text.
+    pub async fn generate(&self) -> Result<String> {
+        let (_, _, calendar) = self.calendar().await?;
+        Ok(calendar.generate())
+    }
+
+    /// Merge the events of this target into an existing calendar, replacing the former events of the address.
+    pub async fn merge(&self, existing: &str) -> Result<IcalCalendar> {
+        let (provider, address, calendar) = self.calendar().await?;
+        garbage_client::merge_calendar(
+            existing,
+            calendar,
+            provider.as_ref(),
+            &address.requested.0,
+            &address.requested.1,
+        )
+    }
+
+    /// Get the paths and the contents of the calendar files of this target.
+    ///
+    /// With `--split-by-type`, there is a file per waste type which is not excluded and collected at the address.
+    pub async fn files(&self) -> Result<Vec<(PathBuf, String)>> {
+        if !self.arguments.split_by_type {
+            return Ok(vec![(self.path.clone(), self.generate().await?)]);
+        }
+        if self.path == Path::new(STDOUT) {
+            bail!("the calendars of the waste types can not be written to stdout");
+        }
+        let (provider, address, waste_data) = self
+            .arguments
+            .schedule
+            .fetch(&self.global_arguments)
+            .await?;
+        let excluded = WasteTypeBitmask::from(&self.arguments);
+        let stem = self
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("calendar"));
+        Ok(WasteType::ALL
+            .into_iter()
+            .filter(|waste_type| {
+                !excluded.contains(waste_type.bitmask())
+                    && !waste_data.dates(*waste_type).is_empty()
+            })
+            .map(|waste_type| {
+                let calendar = garbage_client::get_calendar(
+                    provider.as_ref(),
+                    &address.requested.0,
+                    &address.requested.1,
+                    waste_data.clone(),
+                    excluded | waste_type.bitmask().not(),
+                    &RenderOptions::from(&self.arguments),
+                );
+                let path = self.path.with_file_name(format!("{stem}-{waste_type}.ics"));
+                (path, calendar.generate())
+            })
+            .collect())
+    }
+
+    /// Describe this target in messages, e.g. "the profile \"home\"".
+    pub fn describe(&self) -> String {
+        match &self.profile {
+            Some(name) => format!("the profile \"{name}\""),
+            None if self.path == Path::new(STDOUT) => String::from("stdout"),
+            None => format!("the file {}", self.path.display()),
+        }
+    }
+}
+
+/// Write a file atomically, so that a half-written file is never read, e.g. by a synchronization client.
+///
+/// The path `-` writes to stdout.
+pub fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    if path == Path::new(STDOUT) {
+        stdout().write_all(content)?;
+        return Ok(());
+    }
+    write_atomically(path, content)
+}
+
+/// Pack files into a ZIP file by their file names.
+fn bundle(files: &[(PathBuf, String)]) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (path, content) in files {
+        let name = path
+            .file_name()
+            .with_context(|| format!("the path {} is not a file", path.display()))?;
+        zip.start_file(name.to_string_lossy(), FileOptions::default())?;
+        zip.write_all(content.as_bytes())?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Merge the events of the targets into an existing calendar file, which is created if it does not exist.
+///
+/// The file is only written if the events of all targets could be generated.
+async fn merge(targets: &[Target], path: &Path) -> Result<()> {
+    let mut calendar = match read_to_string(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == ErrorKind::NotFound => String::new(),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("the file {} cannot be read", path.display()))
+        }
+    };
+    for target in targets {
+        calendar = target
+            .merge(&calendar)
+            .await
+            .with_context(|| {
+                format!(
+                    "the calendar of {} cannot be merged into the file {}",
+                    target.describe(),
+                    path.display()
+                )
+            })?
+            .generate();
+    }
+    write_file(path, calendar.as_bytes())
+}
+
+/// Get the calendar files which are written for the options.
+///
+/// These are the outputs of all profiles with `--all`, the output of a profile, or the file of `--output`,
+/// which is `calendar.ics` in the current working directory by default.
+pub fn targets(args: &Arguments, global_arguments: &GlobalArguments) -> Result<Vec<Target>> {
+    let target = |name: &str, profile: &Profile| {
+        Ok(Target {
+            profile: Some(String::from(name)),
+            arguments: Arguments {
+                split_by_type: args.split_by_type,
+                ..profile.fetch_arguments()?
+            },
+            global_arguments: profile.global_arguments(global_arguments),
+            path: args.output.clone().unwrap_or_else(|| profile.output(name)),
+        })
+    };
+    if args.all {
+        let config = Config::load(global_arguments)?;
+        if config.profiles.is_empty() {
+            bail!("the configuration file does not contain any profiles");
+        }
+        return config
+            .profiles
+            .iter()
+            .map(|(name, profile)| target(name, profile))
+            .collect();
+    }
+    if let Some(name) = args.schedule.profile() {
+        let config = Config::load(global_arguments)?;
+        return Ok(vec![target(name, config.profile(name)?)?]);
+    }
+    let path = match &args.output {
+        Some(path) => path.clone(),
+        None => current_dir()?.join("calendar.ics"),
+    };
+    Ok(vec![Target {
+        profile: None,
+        arguments: args.clone(),
+        global_arguments: global_arguments.clone(),
+        path,
+    }])
+}
+
+/// Get the calendar and write it to the file `calendar.ics` in the current working directory, or to `--output`.
+///
+/// The calendar of a profile is written to its output, `--all` writes the calendars of all profiles.
+/// With `--bundle`, the calendar files are only written into the ZIP file if all of them could be generated.
+pub async fn run(args: Arguments, global_arguments: &GlobalArguments) -> Result<()> {
+    let targets = targets(&args, global_arguments)?;
+    if let Some(path) = &args.merge_into {
+        return merge(&targets, path).await;
+    }
+    let mut bundled = Vec::new();
+    let mut failed = Vec::new();
+    for target in &targets {
+        let result = async {
+            let files = target.files().await?;
+            if args.bundle.is_some() {
+                bundled.extend(files);
+                return Ok(());
+            }
+            for (path, calendar) in files {
+                write_file(&path, calendar.as_bytes())?;
+            }
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(error) = result {
+            let error = error.context(format!(
+                "the calendar of {} cannot be written",
+                target.describe()
+            ));
+            if targets.len() == 1 {
+                return Err(error);
+            }
+            eprintln!("Error: {error:?}");
+            failed.push(target.describe());
+        }
+    }
+    if !failed.is_empty() {
+        bail!("the calendars of {} cannot be written", failed.join(", "));
+    }
+    if let Some(path) = &args.bundle {
+        write_file(path, &bundle(&bundled)?)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use std::{
+    fs::{create_dir_all, read_to_string, OpenOptions},
+    io::Write,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use clap::Args;
+use inquire::{
+    validator::{StringValidator, Validation},
+    Confirm, MultiSelect, Text,
+};
+use kgc_core::{
+    address::suggest_streets, agenda::upcoming, garbage_client::WasteType, provider,
+    provider::DEFAULT_PROVIDER,
+};
+
+use crate::{
+    command::next::describe,
+    config::{Config, Profile},
+    GlobalArguments,
+};
+
+/// The URL of the public server.
+static DEFAULT_SERVER_URL: &str = "https://karlsruhe-garbage-calendar.fly.dev";
+/// The number of days from today whose collections are shown for confirmation.
+static PREVIEW_DAYS: i64 = 28;
+/// The number of streets which are suggested while the street is typed.
+static SUGGESTIONS: usize = 8;
+
+#[derive(Debug, Args)]
+pub struct Arguments {
+    /// the municipality whose official website is read
+    #[arg(long, default_value_t = String::from(DEFAULT_PROVIDER))]
+    pub provider: String,
+    /// the URL of the server whose subscription URL is printed
+    #[arg(long, default_value_t = String::from(DEFAULT_SERVER_URL))]
+    pub server_url: String,
+}
+
+/// Ask for an address and the options of its calendar and write them as a profile to the configuration file.
+pub async fn run(args: Arguments, global_arguments: &GlobalArguments) -> Result<()> {
+    let locale = global_arguments.locale;
+    let provider = provider::provider(&args.provider, global_arguments.url.as_deref())
+        .ok_or_else(|| anyhow!("the provider \"{}\" is not supported", args.provider))?;
+    let streets = Arc::new(match provider.get_streets().await {
+        Ok(streets) => streets,
+        Err(error) => {
+            eprintln!(
+                "Warning: the street list cannot be read, so no streets are suggested: {error:#}"
+            );
+            Vec::new()
+        }
+    });
+    let mut profile = Profile {
+        provider: (args.provider != DEFAULT_PROVIDER).then(|| args.provider.clone()),
+        ..Profile::default()
+    };
+    let waste_data = loop {
+        let streets = streets.clone();
+        let suggest = move |input: &str| {
+            Ok(suggest_streets(&streets, input, SUGGESTIONS)
+                .into_iter()
+                .cloned()
+                .collect())
+        };
+        profile.street = Text::new("Street:")
+            .with_initial_value(&profile.street)
+            .with_autocomplete(suggest)
+            .with_help_message("the streets of the official street list are suggested")
+            .prompt()?;
+        profile.street_number = Text::new("Street number:")
+            .with_initial_value(&profile.street_number)
+            .prompt()?;
+        println!(
+            "Fetching the collection dates of {} {}…",
+            profile.street, profile.street_number
+        );
+        let (_, address, waste_data) =
+            match profile.schedule_arguments()?.fetch(global_arguments).await {
+                Ok(fetched) => fetched,
+                Err(error) => {
+                    eprintln!("Error: {error:#}");
+                    continue;
+                }
+            };
+        profile.street = address.street;
+        let entries = upcoming(
+            &waste_data,
+            Local::now().date_naive(),
+            PREVIEW_DAYS,
+            &[],
+            locale,
+        );
+        println!(
+            "The collections of {} {} in the next {PREVIEW_DAYS} days:",
+            profile.street, profile.street_number
+        );
+        for entry in &entries {
+            println!("  {}", describe(entry, locale));
+        }
+        if entries.is_empty() {
+            println!("  none");
+        }
+        if Confirm::new("Are these the collections of your address?")
+            .with_default(true)
+            .prompt()?
+        {
+            break waste_data;
+        }
+    };
+    let names: Vec<&str> = WasteType::ALL
+        .iter()
+        .map(|waste_type| locale.waste_type(*waste_type))
+        .collect();
+    let defaults: Vec<usize> = WasteType::ALL
+        .iter()
+        .enumerate()
+        .filter(|(_, waste_type)| !waste_data.collections(**waste_type).is_empty())
+        .map(|(index, _)| index)
+        .collect();
+    let selected: Vec<WasteType> = MultiSelect::new("Waste types in the calendar:", names)
+        .with_default(&defaults)
+        .raw_prompt()?
+        .into_iter()
+        .map(|option| WasteType::ALL[option.index])
+        .collect();
+    profile.exclude = WasteType::ALL
+        .into_iter()
+        .filter(|waste_type| !selected.contains(waste_type))
+        .collect();
+    profile.mark_shifted = Confirm::new("Add separate events for shifted collections?")
+        .with_default(true)
+        .with_help_message("e.g. if a holiday shifts a collection from the usual weekday")
+        .prompt()?;
+    let remind = Confirm::new("Remind you on the evening before the collections?")
+        .with_default(false)
+        .with_help_message("a line for your crontab which runs `kgc remind` is printed")
+        .prompt()?;
+    let path = Config::path(global_arguments)?;
+    let config = if path.exists() {
+        Config::read(&path)?
+    } else {
+        Config::default()
+    };
+    let name = Text::new("Name of the profile:")
+        .with_default("home")
+        .with_validator(ProfileNameValidator(
+            config.profiles.keys().cloned().collect(),
+        ))
+        .prompt()?;
+    let mut new_config = Config::default();
+    new_config.profiles.insert(name.clone(), profile.clone());
+    if let Some(directory) = path.parent() {
+        create_dir_all(directory)?;
+    }
+    let separator = match read_to_string(&path) {
+        Ok(content) if !content.trim().is_empty() => "\n",
+        _ => "",
+    };
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?
+        .write_all(format!("{separator}{}", toml::to_string(&new_config)?).as_bytes())?;
+    println!(
+        "The profile \"{name}\" has been written to {}.",
+        path.display()
+    );
+    println!("Write its calendar with: kgc fetch {name}");
+    println!(
+        "Subscribe to its calendar on the server with: {}",
+        profile.subscription_url(&args.server_url)?
+    );
+    if remind {
+        let types = if profile.exclude.is_empty() {
+            String::new()
+        } else {
+            let selected: Vec<String> = selected.iter().map(ToString::to_string).collect();
+            format!(" --type {}", selected.join(","))
+        };
+        println!("Add this line to your crontab (`crontab -e`) to be reminded at 18:00:");
+        println!("0 18 * * * kgc remind {name}{types}");
+    }
+    Ok(())
+}
+
+/// Accept names of profiles which are not configured yet and which are bare keys in TOML.
+#[derive(Debug, Clone)]
+struct ProfileNameValidator(Vec<String>);
+
+impl StringValidator for ProfileNameValidator {
+    fn validate(&self, input: &str) -> Result<Validation, inquire::CustomUserError> {
+        if input.is_empty()
+            || !input
+                .chars()
+                .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_')
+        {
+            return Ok(Validation::Invalid(
+                "only letters, digits, \"-\" and \"_\" are allowed".into(),
+            ));
+        }
+        if self.0.iter().any(|name| name == input) {
+            return Ok(Validation::Invalid(
+                format!("the profile \"{input}\" already exists").into(),
+            ));
+        }
+        Ok(Validation::Valid)
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    env,
+    io::{stdout, IsTerminal},
+};
+
+use anstyle::{AnsiColor, Style};
+use anyhow::Result;
+use chrono::Local;
+use clap::{Args, ValueEnum};
+use kgc_core::{
+    agenda::{upcoming, Entry},
+    garbage_client::WasteType,
+    locale::Locale,
+};
+
+use crate::{command::ScheduleArguments, GlobalArguments};
+
+#[derive(Debug, Args)]
+pub struct Arguments {
+    #[command(flatten)]
+    pub schedule: ScheduleArguments,
+    /// list the collections until this number of days from today
+    #[arg(long, default_value_t = 14)]
+    pub days: i64,
+    /// only list these waste types (residual, organic, recyclable, paper, bulky or hazardous)
+    #[arg(long = "type", value_delimiter = ',')]
+    pub waste_types: Vec<WasteType>,
+    /// the output format
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    pub format: Format,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Format {
+    /// a table, which is coloured in a terminal
+    Table,
+    Text,
+    Json,
+}
+
+/// Print the upcoming collections.
+pub async fn run(args: Arguments, global_arguments: &GlobalArguments) -> Result<()> {
+    let (schedule, global_arguments) = args.schedule.resolve(global_arguments)?;
+    let locale = global_arguments.locale;
+    let (_, _, waste_data) = schedule.fetch(&global_arguments).await?;
+    let entries = upcoming(
+        &waste_data,
+        Local::now().date_naive(),
+        args.days,
+        &args.waste_types,
+        locale,
+    );
+    match args.format {
+        Format::Table => {
+            let colored = stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+            print_table(&entries, locale, colored);
+        }
+        Format::Text => {
+            for entry in &entries {
+                println!("{}", describe(entry, locale));
+            }
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+    }
+    Ok(())
+}
+
+/// Describe an entry in a single line, e.g. "Freitag, 16.06.2023 (morgen): Restmüll".
+pub fn describe(entry: &Entry, locale: Locale) -> String {
+    let mut line = format!(
+        "{}, {} ({}): {}",
+        locale.weekday(entry.weekday),
+        locale.format_date(entry.date),
+        entry.describe_days_until(locale),
+        waste_type(entry, locale)
+    );
+    if let Some(note) = &entry.note {
+        line = format!("{line} – {note}");
+    }
+    line
+}
+
+/// Get the name of the waste type of an entry, predicted ones are marked with a question mark.
+fn waste_type(entry: &Entry, locale: Locale) -> String {
+    let name = locale.waste_type(entry.waste_type);
+    if entry.predicted {
+        format!("{name} (?)")
+    } else {
+        String::from(name)
+    }
+}
+
+/// Print the entries as a table with aligned columns.
+///
+/// If coloured, the waste types get the colours of their bins and collections within a day are highlighted.
+fn print_table(entries: &[Entry], locale: Locale, colored: bool) {
+    let header = match locale {
+        Locale::German => ["Datum", "Wochentag", "Wann", "Abfallart", "Hinweis"],
+        Locale::English => ["Date", "Weekday", "When", "Waste type", "Note"],
+    };
+    let rows: Vec<[String; 5]> = entries
+        .iter()
+        .map(|entry| {
+            [
+                locale.format_date(entry.date),
+                String::from(locale.weekday(entry.weekday)),
+                entry.describe_days_until(locale),
+                waste_type(entry, locale),
+                entry.note.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    let mut widths = header.map(|cell| cell.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let pad = |cell: &str, width: usize| {
+        let padding = width.saturating_sub(cell.chars().count());
+        format!("{cell}{}", " ".repeat(padding))
+    };
+    let paint = |text: String, style: Style| {
+        if colored {
+            format!("{}{text}{}", style.render(), style.render_reset())
+        } else {
+            text
+        }
+    };
+    let line = |cells: Vec<String>, styles: [Style; 5]| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .zip(styles)
+            .map(|((cell, width), style)| paint(pad(cell, width), style))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    };
+    line(
+        header.iter().map(|cell| cell.to_string()).collect(),
+        [Style::new().bold(); 5],
+    );
+    for (entry, row) in entries.iter().zip(rows) {
+        let urgency = match entry.days_until {
+            0 | 1 => Style::new().bold(),
+            _ => Style::new(),
+        };
+        line(
+            row.to_vec(),
+            [
+                urgency,
+                urgency,
+                urgency,
+                Style::new().fg_color(Some(bin_color(entry.waste_type).into())),
+                Style::new().fg_color(Some(AnsiColor::Yellow.into())),
+            ],
+        );
+    }
+}
+
+/// Get the colour of the bin of a waste type.
+fn bin_color(waste_type: WasteType) -> AnsiColor {
+    match waste_type {
+        WasteType::Residual => AnsiColor::BrightBlack,
+        WasteType::Organic => AnsiColor::Green,
+        WasteType::Recyclable => AnsiColor::Yellow,
+        WasteType::Paper => AnsiColor::Blue,
+        WasteType::Bulky => AnsiColor::Magenta,
+        WasteType::Hazardous => AnsiColor::Red,
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::{bail, Result};
+use chrono::Local;
+use clap::Args;
+use kgc_core::{agenda::upcoming, garbage_client::WasteType};
+use tokio::process::Command;
+
+use crate::{command::ScheduleArguments, GlobalArguments};
+
+/// The template of the message if none is given, e.g. "Restmüll: morgen (Freitag, 16.06.2023)".
+static DEFAULT_TEMPLATE: &str = "{type}: {when} ({weekday}, {date})";
+
+#[derive(Debug, Args)]
+pub struct Arguments {
+    #[command(flatten)]
+    pub schedule: ScheduleArguments,
+    /// remind of the collections within this number of days from tomorrow, 0 reminds of today's collections
+    #[arg(long, default_value_t = 1)]
+    pub offset: i64,
+    /// only remind of these waste types (residual, organic, recyclable, paper, bulky or hazardous)
+    #[arg(long = "type", value_delimiter = ',')]
+    pub waste_types: Vec<WasteType>,
+    /// the template of the message, in which {type}, {date}, {weekday}, {when} and {note} are replaced
+    #[arg(long)]
+    pub template: Option<String>,
+    /// the shell command which is run for every due collection with the environment variables KGC_TYPE, KGC_DATE and more
+    #[arg(long)]
+    pub exec: Option<String>,
+}
+
+/// Print a message and run the command for every collection which is due within the offset.
+///
+/// Returns whether any collection is due.
+pub async fn run(args: Arguments, global_arguments: &GlobalArguments) -> Result<bool> {
+    let (schedule, global_arguments) = args.schedule.resolve(global_arguments)?;
+    let locale = global_arguments.locale;
+    let template = args.template.as_deref().unwrap_or(DEFAULT_TEMPLATE);
+    let (_, _, waste_data) = schedule.fetch(&global_arguments).await?;
+    let entries: Vec<_> = upcoming(
+        &waste_data,
+        Local::now().date_naive(),
+        args.offset,
+        &args.waste_types,
+        locale,
+    )
+    .into_iter()
+    .filter(|entry| entry.days_until >= args.offset.min(1))
+    .collect();
+    for entry in &entries {
+        let message = entry.render(template, locale);
+        println!("{message}");
+        let Some(exec) = &args.exec else {
+            continue;
+        };
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(exec)
+            .env("KGC_TYPE", entry.waste_type.to_string())
+            .env("KGC_TYPE_NAME", locale.waste_type(entry.waste_type))
+            .env("KGC_DATE", entry.date.to_string())
+            .env("KGC_WEEKDAY", locale.weekday(entry.weekday))
+            .env("KGC_DAYS_UNTIL", entry.days_until.to_string())
+            .env("KGC_NOTE", entry.note.as_deref().unwrap_or_default())
+            .env("KGC_PREDICTED", entry.predicted.to_string())
+            .env("KGC_MESSAGE", &message)
+            .status()
+            .await?;
+        if !status.success() {
+            bail!(
+                "the command for the collection of {} on {} failed with {status}",
+                entry.waste_type,
+                entry.date
+            );
+        }
+    }
+    Ok(!entries.is_empty())
+}
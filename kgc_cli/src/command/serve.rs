@@ -0,0 +1,23 @@
+use anyhow::Result;
+use clap::Args;
+
+use crate::GlobalArguments;
+
+#[derive(Debug, Args)]
+pub struct Arguments {
+    /// the port the server binds to
+    #[arg(long, default_value_t = 8008)]
+    pub port: u16,
+}
+
+/// Serve the calendars until the server fails.
+///
+/// The server is further configured by the `KGC_*` environment variables.
+pub async fn run(args: Arguments, global_arguments: &GlobalArguments) -> Result<()> {
+    kgc_server::serve(kgc_server::Options {
+        port: args.port,
+        upstream_url: global_arguments.url.clone(),
+        archive_directory: global_arguments.cache.clone(),
+    })
+    .await
+}
@@ -0,0 +1,438 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Args;
+use kgc_core::{
+    garbage_client::is_uid_of_address,
+    ical::generator::{Emitter, IcalCalendar},
+};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{
+    header::{CONTENT_TYPE, IF_MATCH, IF_NONE_MATCH},
+    Client, Method, RequestBuilder, Response, StatusCode,
+};
+use roxmltree::Document;
+use url::Url;
+
+use crate::{
+    command::fetch::{self, Target},
+    GlobalArguments,
+};
+
+/// The characters which are kept in the names of the objects, which are the UIDs of their events.
+static NAME: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.');
+/// The request for the ETags and the data of the objects of a collection.
+static CALENDAR_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+<d:prop><d:getetag/><c:calendar-data/></d:prop>
+<c:filter><c:comp-filter name="VCALENDAR"/></c:filter>
+</c:calendar-query>"#;
+/// The namespace of the CalDAV elements.
+static CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+
+#[derive(Debug, Args)]
+#[group(skip)]
+pub struct Arguments {
+    #[command(flatten)]
+    pub fetch: fetch::Arguments,
+    /// the URL of the calendar collection on the CalDAV server (e.g. https://dav.example.org/anna/garbage/)
+    #[arg(long)]
+    pub caldav: Url,
+    /// the user name of the CalDAV server
+    #[arg(long)]
+    pub username: Option<String>,
+    /// the password or app password of the CalDAV server, which is better given by the environment variable
+    #[arg(long, env = "KGC_CALDAV_PASSWORD", hide_env_values = true)]
+    pub password: Option<String>,
+}
+
+/// An object of the calendar collection.
+#[derive(Debug, Clone)]
+struct Object {
+    url: Url,
+    etag: Option<String>,
+    data: Option<String>,
+}
+
+/// The changes which bring the collection up to date with the generated objects.
+#[derive(Debug, Default)]
+struct Plan<'a> {
+    /// The names and the data of the new objects.
+    create: Vec<(String, String)>,
+    /// The outdated objects and their new data.
+    update: Vec<(&'a Object, String)>,
+    /// The number of objects which are up to date.
+    unchanged: usize,
+    /// The objects whose events are gone.
+    delete: Vec<&'a Object>,
+}
+
+/// A calendar collection on a CalDAV server.
+struct Collection {
+    client: Client,
+    url: Url,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl Collection {
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let request = self.client.request(method, url);
+        match &self.username {
+            Some(username) => request.basic_auth(username, self.password.as_ref()),
+            None => request,
+        }
+    }
+
+    /// Get the objects of the collection by their decoded names, e.g. `Abfuhrkalender_…@karlsruhe.de.ics`.
+    async fn objects(&self) -> Result<BTreeMap<String, Object>> {
+        let response = self
+            .request(Method::from_bytes(b"REPORT")?, self.url.clone())
+            .header("Depth", "1")
+            .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(CALENDAR_QUERY)
+            .send()
+            .await?;
+        let xml = check(response)?.text().await?;
+        parse_objects(&xml, &self.url)
+            .with_context(|| format!("the response of {} is invalid", self.url))
+    }
+
+    /// Create an object, or replace it if its ETag still matches.
+    async fn put(&self, url: Url, etag: Option<&str>, data: String) -> Result<()> {
+        let request = self
+            .request(Method::PUT, url)
+            .header(CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .body(data);
+        let request = match etag {
+            Some(etag) => request.header(IF_MATCH, etag),
+            None => request.header(IF_NONE_MATCH, "*"),
+        };
+        check(request.send().await?)?;
+        Ok(())
+    }
+
+    /// Delete an object if its ETag still matches.
+    async fn delete(&self, object: &Object) -> Result<()> {
+        let request = self.request(Method::DELETE, object.url.clone());
+        let request = match &object.etag {
+            Some(etag) => request.header(IF_MATCH, etag),
+            None => request,
+        };
+        check(request.send().await?)?;
+        Ok(())
+    }
+}
+
+/// Parse the objects of a multistatus response by their decoded names.
+///
+/// The collection itself and responses without a name are skipped.
+fn parse_objects(xml: &str, base_url: &Url) -> Result<BTreeMap<String, Object>> {
+    let document = Document::parse(xml)?;
+    let mut objects = BTreeMap::new();
+    for response in document
+        .descendants()
+        .filter(|node| node.has_tag_name(("DAV:", "response")))
+    {
+        let text = |name| {
+            response
+                .descendants()
+                .find(|node| node.has_tag_name(name))
+                .and_then(|node| node.text())
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+        };
+        let Some(href) = text(("DAV:", "href")) else {
+            continue;
+        };
+        let url = base_url.join(href)?;
+        let Some(name) = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+        else {
+            continue;
+        };
+        if name.is_empty() {
+            continue;
+        }
+        let name = percent_decode_str(name).decode_utf8_lossy().into_owned();
+        let etag = text(("DAV:", "getetag")).map(String::from);
+        let data = text((CALDAV, "calendar-data")).map(String::from);
+        objects.insert(name, Object { url, etag, data });
+    }
+    Ok(objects)
+}
+
+/// Get the lines of an object which matter for a comparison.
+///
+/// The lines are unfolded and the time stamp is left out, because it is the time the object has been generated.
+fn content_lines(data: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in data.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+        .into_iter()
+        .filter(|line| !line.is_empty() && !line.starts_with("DTSTAMP"))
+        .collect()
+}
+
+/// Decide which objects are created, updated or deleted.
+///
+/// The generated objects are given by their names and their data. Existing objects are only updated if their data
+/// differs, and only the objects which belong to the address (`is_own`) are deleted.
+fn plan<'a>(
+    objects: &'a BTreeMap<String, Object>,
+    generated: Vec<(String, String)>,
+    is_own: impl Fn(&str) -> bool,
+) -> Plan<'a> {
+    let mut plan = Plan::default();
+    let mut names = BTreeSet::new();
+    for (name, data) in generated {
+        match objects.get(&name) {
+            Some(existing)
+                if existing
+                    .data
+                    .as_deref()
+                    .is_some_and(|existing| content_lines(existing) == content_lines(&data)) =>
+            {
+                plan.unchanged += 1;
+            }
+            Some(existing) => plan.update.push((existing, data)),
+            None => plan.create.push((name.clone(), data)),
+        }
+        names.insert(name);
+    }
+    plan.delete = objects
+        .iter()
+        .filter(|(name, _)| !names.contains(*name) && is_own(name))
+        .map(|(_, object)| object)
+        .collect();
+    plan
+}
+
+/// Turn unsuccessful responses into errors.
+fn check(response: Response) -> Result<Response> {
+    if response.status() == StatusCode::PRECONDITION_FAILED {
+        bail!(
+            "the object {} has been changed on the server in the meantime, sync again",
+            response.url()
+        );
+    }
+    Ok(response.error_for_status()?)
+}
+
+/// Write the events of a target as objects of the collection and delete its objects whose events are gone.
+///
+/// Every event is an object which is named after its UID, so only the objects of the address are touched.
+/// Objects which are up to date are not written again.
+async fn sync(target: &Target, collection: &Collection) -> Result<()> {
+    let (provider, address, calendar) = target.calendar().await?;
+    let objects = collection.objects().await?;
+    let mut generated = Vec::new();
+    for event in &calendar.events {
+        let uid = event
+            .properties
+            .iter()
+            .find(|property| property.name == "UID")
+            .and_then(|property| property.value.as_deref())
+            .ok_or_else(|| anyhow!("an event has no UID"))?;
+        let object = IcalCalendar {
+            properties: calendar.properties.clone(),
+            timezones: calendar.timezones.clone(),
+            events: vec![event.clone()],
+            ..IcalCalendar::default()
+        };
+        generated.push((format!("{uid}.ics"), object.generate()));
+    }
+    let (street, street_number) = &address.requested;
+    let plan = plan(&objects, generated, |name| {
+        name.strip_suffix(".ics")
+            .is_some_and(|uid| is_uid_of_address(uid, provider.as_ref(), street, street_number))
+    });
+    let (created, updated, deleted) = (plan.create.len(), plan.update.len(), plan.delete.len());
+    for (name, data) in plan.create {
+        let uid = name.strip_suffix(".ics").unwrap_or(&name);
+        let url = collection
+            .url
+            .join(&format!("{}.ics", utf8_percent_encode(uid, NAME)))?;
+        collection.put(url, None, data).await?;
+    }
+    for (object, data) in plan.update {
+        collection
+            .put(object.url.clone(), object.etag.as_deref(), data)
+            .await?;
+    }
+    for object in plan.delete {
+        collection.delete(object).await?;
+    }
+    println!(
+        "{address}: {created} created, {updated} updated, {} unchanged, {deleted} deleted",
+        plan.unchanged
+    );
+    Ok(())
+}
+
+/// Write the events of the calendars as objects of a CalDAV collection.
+pub async fn run(args: Arguments, global_arguments: &GlobalArguments) -> Result<()> {
+    let unsupported = [
+        ("--output", args.fetch.output.is_some()),
+        ("--split-by-type", args.fetch.split_by_type),
+        ("--bundle", args.fetch.bundle.is_some()),
+        ("--merge-into", args.fetch.merge_into.is_some()),
+    ];
+    for (option, given) in unsupported {
+        if given {
+            bail!("the option {option} is not supported by sync");
+        }
+    }
+    let mut url = args.caldav;
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    let collection = Collection {
+        client: Client::new(),
+        url,
+        username: args.username,
+        password: args.password,
+    };
+    let targets = fetch::targets(&args.fetch, global_arguments)?;
+    let mut failed = Vec::new();
+    for target in &targets {
+        if let Err(error) = sync(target, &collection).await {
+            if targets.len() == 1 {
+                return Err(error);
+            }
+            eprintln!(
+                "Error: {:?}",
+                error.context(format!("{} cannot be synchronized", target.describe()))
+            );
+            failed.push(target.describe());
+        }
+    }
+    if !failed.is_empty() {
+        bail!("{} cannot be synchronized", failed.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use url::Url;
+
+    use crate::command::sync::{content_lines, parse_objects, plan, Object};
+
+    fn objects() -> BTreeMap<String, Object> {
+        let base_url = Url::parse("https://dav.example.org/anna/garbage/").unwrap();
+        parse_objects(include_str!("sync/tests/multistatus.xml"), &base_url).unwrap()
+    }
+
+    /// Get the recorded data of an object, generated again at another time and without folded lines.
+    fn regenerated(objects: &BTreeMap<String, Object>, name: &str) -> String {
+        objects[name]
+            .data
+            .as_deref()
+            .unwrap()
+            .replace("\r\n", "\n")
+            .replace("akal/\n akal.php", "akal/akal.php")
+            .replace(
+                "DTSTAMP;TZID=Europe/Berlin:20261004T081502",
+                "DTSTAMP;TZID=Europe/Berlin:20261018T160810",
+            )
+    }
+
+    /// Test whether the objects of a multistatus response are parsed by their decoded names.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_parse_objects() {
+        let objects = objects();
+        assert_eq!(
+            objects.keys().collect::<Vec<_>>(),
+            vec![
+                "Abfuhrkalender_Kaiserstraße_1_Papier@karlsruhe.de.ics",
+                "Abfuhrkalender_Kaiserstraße_1_Restmüll@karlsruhe.de.ics",
+                "Abfuhrkalender_Kaiserstraße_1_Sperrmüll@karlsruhe.de.ics",
+                "birthday-anna.ics",
+            ]
+        );
+        let object = &objects["Abfuhrkalender_Kaiserstraße_1_Papier@karlsruhe.de.ics"];
+        assert_eq!(
+            object.url.as_str(),
+            "https://dav.example.org/anna/garbage/Abfuhrkalender_Kaiserstra%C3%9Fe_1_Papier%40karlsruhe.de.ics"
+        );
+        assert_eq!(
+            object.etag.as_deref(),
+            Some("\"9e1d2c3b4a5f60718293a4b5c6d7e8f9\"")
+        );
+        let data = object.data.as_deref().unwrap();
+        assert!(data.starts_with("BEGIN:VCALENDAR"));
+        assert!(data.contains("RDATE;VALUE=DATE:20261024"));
+        let base_url = Url::parse("https://dav.example.org/anna/garbage/").unwrap();
+        assert!(parse_objects("<multistatus", &base_url).is_err());
+    }
+
+    /// Test whether the lines are unfolded and the time stamp is left out.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_content_lines() {
+        assert_eq!(
+            content_lines("BEGIN:VEVENT\r\nDTSTAMP:20261018T160810\r\nDESCRIPTION:a\r\n b\r\n\tc\r\nEND:VEVENT\r\n"),
+            vec!["BEGIN:VEVENT", "DESCRIPTION:abc", "END:VEVENT"]
+        );
+    }
+
+    /// Test whether only changed objects are updated and only gone objects of the address are deleted.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_plan() {
+        let objects = objects();
+        let restmuell = "Abfuhrkalender_Kaiserstraße_1_Restmüll@karlsruhe.de.ics";
+        let papier = "Abfuhrkalender_Kaiserstraße_1_Papier@karlsruhe.de.ics";
+        let wertstoff = "Abfuhrkalender_Kaiserstraße_1_Wertstoff@karlsruhe.de.ics";
+        let generated = vec![
+            (restmuell.to_string(), regenerated(&objects, restmuell)),
+            (
+                papier.to_string(),
+                regenerated(&objects, papier).replace("20261024", "20261107"),
+            ),
+            (
+                wertstoff.to_string(),
+                String::from("BEGIN:VCALENDAR\nEND:VCALENDAR\n"),
+            ),
+        ];
+        let plan = plan(&objects, generated, |name| {
+            name.starts_with("Abfuhrkalender_Kaiserstraße_1_")
+        });
+        assert_eq!(plan.unchanged, 1);
+        assert_eq!(
+            plan.create
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec![wertstoff]
+        );
+        assert_eq!(plan.update.len(), 1);
+        assert_eq!(plan.update[0].0.url, objects[papier].url);
+        assert!(plan.update[0].1.contains("RDATE;VALUE=DATE:20261107"));
+        assert_eq!(
+            plan.delete
+                .iter()
+                .map(|object| object.url.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                objects["Abfuhrkalender_Kaiserstraße_1_Sperrmüll@karlsruhe.de.ics"]
+                    .url
+                    .as_str()
+            ]
+        );
+    }
+}
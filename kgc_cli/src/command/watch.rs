@@ -0,0 +1,269 @@
+use std::{fs::read_to_string, io::ErrorKind, path::Path, time::Duration};
+
+use anyhow::{bail, Result};
+use chrono::Local;
+use clap::Args;
+use kgc_core::{
+    diff::diff,
+    garbage_client::{is_same_calendar, parse_calendar},
+};
+use rand::Rng;
+use tokio::{process::Command, signal, time::sleep};
+
+use crate::{
+    command::fetch::{self, write_file, Target},
+    GlobalArguments,
+};
+
+/// The largest number of minutes of the interval and the jitter, a year, which keeps the delay in seconds from overflowing.
+static MAX_MINUTES: u64 = 366 * 24 * 60;
+
+#[derive(Debug, Args)]
+#[group(skip)]
+pub struct Arguments {
+    #[command(flatten)]
+    pub fetch: fetch::Arguments,
+    /// the number of minutes between two fetches
+    #[arg(long, default_value_t = 360, value_parser = clap::value_parser!(u64).range(1..=MAX_MINUTES))]
+    pub interval_minutes: u64,
+    /// at most this number of minutes is randomly added to every interval, so that watchers do not fetch at the same time
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(0..=MAX_MINUTES))]
+    pub jitter_minutes: u64,
+    /// the shell command which is run when a calendar file changed with the environment variables KGC_PATH, KGC_PROFILE and KGC_CHANGES
+    #[arg(long)]
+    pub exec: Option<String>,
+}
+
+/// Fetch the calendars on a schedule and rewrite their files when their content changed, until SIGTERM or SIGINT is received.
+pub async fn run(args: Arguments, global_arguments: &GlobalArguments) -> Result<()> {
+    if args.fetch.bundle.is_some() {
+        bail!("the option --bundle is not supported by watch");
+    }
+    if args.fetch.merge_into.is_some() {
+        bail!("the option --merge-into is not supported by watch");
+    }
+    let targets = fetch::targets(&args.fetch, global_arguments)?;
+    let watch = async {
+        loop {
+            for target in &targets {
+                if let Err(error) = refresh(target, &args).await {
+                    log(&format!("Error: {} failed: {error:#}", target.describe()));
+                }
+            }
+            let jitter = rand::thread_rng().gen_range(0..=args.jitter_minutes * 60);
+            let delay = Duration::from_secs(args.interval_minutes * 60 + jitter);
+            log(&format!(
+                "the next fetch is in {} minutes",
+                delay.as_secs() / 60
+            ));
+            sleep(delay).await;
+        }
+    };
+    tokio::select! {
+        _ = watch => Ok(()),
+        result = shutdown() => {
+            result?;
+            log("stopped");
+            Ok(())
+        }
+    }
+}
+
+/// Fetch the calendars of a target and rewrite their files if their content changed.
+async fn refresh(target: &Target, args: &Arguments) -> Result<()> {
+    for (path, calendar) in target.files().await? {
+        refresh_file(target, &path, &calendar, args).await?;
+    }
+    Ok(())
+}
+
+/// Rewrite a calendar file if its content changed.
+///
+/// The changes of the collection schedule are logged and passed to the command of `--exec`.
+async fn refresh_file(
+    target: &Target,
+    path: &Path,
+    calendar: &str,
+    args: &Arguments,
+) -> Result<()> {
+    let old = match read_to_string(path) {
+        Ok(old) => Some(old),
+        Err(error) if error.kind() == ErrorKind::NotFound => None,
+        Err(error) => return Err(error.into()),
+    };
+    if old
+        .as_deref()
+        .is_some_and(|old| is_same_calendar(old, calendar))
+    {
+        return Ok(());
+    }
+    write_file(path, calendar.as_bytes())?;
+    let locale = target.global_arguments.locale;
+    let changes: Vec<String> = match &old {
+        Some(old) => {
+            let provider = target
+                .arguments
+                .schedule
+                .provider(&target.global_arguments)?;
+            diff(
+                &parse_calendar(old, provider.as_ref())?,
+                &parse_calendar(calendar, provider.as_ref())?,
+            )
+        }
+        .iter()
+        .map(|change| change.describe(locale))
+        .collect(),
+        None => Vec::new(),
+    };
+    log(&format!(
+        "{} has been {}",
+        path.display(),
+        if old.is_some() { "updated" } else { "created" }
+    ));
+    for change in &changes {
+        log(&format!("  {change}"));
+    }
+    if let Some(exec) = &args.exec {
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(exec)
+            .env("KGC_PATH", path)
+            .env("KGC_PROFILE", target.profile.as_deref().unwrap_or_default())
+            .env("KGC_CHANGES", changes.join("\n"))
+            .status()
+            .await?;
+        if !status.success() {
+            bail!("the command failed with {status}");
+        }
+    }
+    Ok(())
+}
+
+/// Wait for SIGTERM, e.g. from systemd, or SIGINT.
+#[cfg(unix)]
+async fn shutdown() -> Result<()> {
+    let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+    tokio::select! {
+        _ = terminate.recv() => Ok(()),
+        result = signal::ctrl_c() => Ok(result?),
+    }
+}
+
+/// Wait for Ctrl+C.
+#[cfg(not(unix))]
+async fn shutdown() -> Result<()> {
+    Ok(signal::ctrl_c().await?)
+}
+
+/// Print a message with the current time to stderr.
+fn log(message: &str) {
+    eprintln!("[{}] {message}", Local::now().format("%Y-%m-%d %H:%M:%S"));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs};
+
+    use chrono::NaiveDate;
+    use clap::Parser;
+    use kgc_core::{
+        garbage_client::{get_calendar, WasteData, WasteTypeBitmask},
+        ical::generator::Emitter,
+        provider::karlsruhe::KarlsruheProvider,
+        render::RenderOptions,
+    };
+
+    use crate::{
+        command::{
+            fetch::Target,
+            watch::{refresh_file, Arguments},
+        },
+        Cli, Command,
+    };
+
+    fn arguments(args: &[&str]) -> Result<(Target, Arguments), clap::Error> {
+        let cli = Cli::try_parse_from(["kgc", "watch", "Kaiserstraße", "1"].iter().chain(args))?;
+        let Command::Watch(arguments) = cli.command else {
+            unreachable!();
+        };
+        let target = Target {
+            profile: None,
+            arguments: arguments.fetch.clone(),
+            global_arguments: cli.global_arguments,
+            path: temp_dir().join("kgc_test_watch.ics"),
+        };
+        Ok((target, *arguments))
+    }
+
+    fn calendar(paper_waste: &[u32]) -> String {
+        let waste_data = WasteData {
+            paper_waste: paper_waste
+                .iter()
+                .map(|day| NaiveDate::from_ymd_opt(2023, 6, *day).unwrap())
+                .collect(),
+            ..WasteData::default()
+        };
+        get_calendar(
+            &KarlsruheProvider::default(),
+            "Kaiserstraße",
+            "1",
+            waste_data,
+            WasteTypeBitmask::none(),
+            &RenderOptions::default(),
+        )
+        .generate()
+    }
+
+    /// Test whether a calendar file is only rewritten if its content changed, and whether the hook gets the changes.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_refresh_file() {
+        let directory = temp_dir().join("kgc_test_watch");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("calendar.ics");
+        let changes = directory.join("calendar.ics.changes");
+        let (target, args) = arguments(&[
+            "--exec",
+            "printf '%s' \"$KGC_CHANGES\" > \"$KGC_PATH.changes\"",
+        ])
+        .unwrap();
+
+        let created = calendar(&[2, 16, 23, 30]);
+        refresh_file(&target, &path, &created, &args).await.unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), created);
+        assert_eq!(fs::read_to_string(&changes).unwrap(), "");
+
+        fs::remove_file(&changes).unwrap();
+        refresh_file(&target, &path, &calendar(&[2, 16, 23, 30]), &args)
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), created);
+        assert!(!changes.exists());
+
+        let updated = calendar(&[2, 17, 30]);
+        refresh_file(&target, &path, &updated, &args).await.unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), updated);
+        assert_eq!(
+            fs::read_to_string(&changes).unwrap(),
+            "Papier: Termin vom 16.06.2023 auf den 17.06.2023 verschoben\nPapier: Termin am 23.06.2023 entfällt"
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    /// Test whether the interval and the jitter are limited, so that the delay cannot overflow.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_arguments_minutes() {
+        let (_, args) = arguments(&[]).unwrap();
+        assert_eq!((args.interval_minutes, args.jitter_minutes), (360, 30));
+        assert!(arguments(&["--interval-minutes", "0"]).is_err());
+        assert!(arguments(&["--interval-minutes", "527040"]).is_ok());
+        assert!(arguments(&["--interval-minutes", "527041"]).is_err());
+        assert!(arguments(&["--jitter-minutes", "0"]).is_ok());
+        assert!(arguments(&["--jitter-minutes", "18446744073709551615"]).is_err());
+    }
+}
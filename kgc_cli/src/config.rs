@@ -0,0 +1,433 @@
+use std::{
+    collections::BTreeMap,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use kgc_core::{
+    garbage_client::WasteType,
+    locale::Locale,
+    provider::DEFAULT_PROVIDER,
+    render::{parse_time_of_day, BlackoutMode, RotationMode},
+};
+use serde::{Deserialize, Serialize};
+use toml::value::Datetime;
+use url::Url;
+
+use crate::{
+    command::{fetch, ScheduleArguments},
+    GlobalArguments,
+};
+
+/// The path of the configuration file in the configuration directory, e.g. `~/.config/kgc/config.toml`.
+static CONFIG_PATH: &str = "kgc/config.toml";
+
+/// The configuration file, which holds the named profiles as `[profile.<name>]` tables.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default, rename = "profile")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// A named address with the options of its calendar, e.g. `home`.
+///
+/// The values are written like the options of `fetch`, e.g. `skip = ["2024-08-01..2024-08-21"]`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    pub street: String,
+    pub street_number: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub district: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub look_back_days: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub predict_days: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub official_calendar: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge_strategy: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub static_dataset: Option<PathBuf>,
+    /// The waste types whose collection dates are excluded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<WasteType>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub mark_shifted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<Datetime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Datetime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub horizon_days: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rotation: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation_mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotation_start: Option<Datetime>,
+    /// The reminders before every collection, e.g. `alarms = ["1d", "6h"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alarms: Vec<String>,
+    /// The time at which the bins have to be out, e.g. `time-of-day = "06:00"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_of_day: Option<String>,
+    /// The locale of the text output, which overrides the option `--locale`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<Locale>,
+    /// The path of the calendar file, `<name>.ics` in the current working directory by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+}
+
+impl Config {
+    /// Read the configuration file of the option `--config`, or the one in the configuration directory.
+    pub fn load(global_arguments: &GlobalArguments) -> Result<Self> {
+        Self::read(&Self::path(global_arguments)?)
+    }
+
+    /// Get the path of the configuration file of the option `--config`, or the one in the configuration directory.
+    pub fn path(global_arguments: &GlobalArguments) -> Result<PathBuf> {
+        match &global_arguments.config {
+            Some(path) => Ok(path.clone()),
+            None => Ok(dirs::config_dir()
+                .ok_or_else(|| anyhow!("the configuration directory is unknown"))?
+                .join(CONFIG_PATH)),
+        }
+    }
+
+    /// Read a configuration file.
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = read_to_string(path)
+            .with_context(|| format!("the configuration file {} cannot be read", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("the configuration file {} is invalid", path.display()))
+    }
+
+    /// Get a profile by its name.
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| {
+            let names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            anyhow!(
+                "the profile \"{name}\" does not exist, the configured profiles are: {}",
+                names.join(", ")
+            )
+        })
+    }
+}
+
+impl Profile {
+    /// Get the options which select the address and the sources of its schedule.
+    pub fn schedule_arguments(&self) -> Result<ScheduleArguments> {
+        Ok(ScheduleArguments {
+            street: Some(self.street.clone()),
+            street_number: Some(self.street_number.clone()),
+            district: self.district.clone(),
+            provider: self
+                .provider
+                .clone()
+                .unwrap_or_else(|| String::from(DEFAULT_PROVIDER)),
+            look_back_days: self.look_back_days.unwrap_or(365),
+            predict_days: self.predict_days,
+            official_calendar: self.official_calendar.clone(),
+            merge_strategy: self
+                .merge_strategy
+                .as_deref()
+                .map(parse)
+                .transpose()?
+                .unwrap_or_default(),
+            from_html: None,
+            source: self
+                .source
+                .iter()
+                .map(|kind| parse(kind))
+                .collect::<Result<_>>()?,
+            static_dataset: self.static_dataset.clone(),
+        })
+    }
+
+    /// Get the options of `fetch` for this profile.
+    pub fn fetch_arguments(&self) -> Result<fetch::Arguments> {
+        let excluded = |waste_type| self.exclude.contains(&waste_type);
+        Ok(fetch::Arguments {
+            schedule: self.schedule_arguments()?,
+            all: false,
+            output: None,
+            split_by_type: false,
+            bundle: None,
+            merge_into: None,
+            exclude_residual: excluded(WasteType::Residual),
+            exclude_organic: excluded(WasteType::Organic),
+            exclude_recyclable: excluded(WasteType::Recyclable),
+            exclude_paper: excluded(WasteType::Paper),
+            exclude_bulky: excluded(WasteType::Bulky),
+            exclude_hazardous: excluded(WasteType::Hazardous),
+            mark_shifted: self.mark_shifted,
+            from: date(self.from.as_ref())?,
+            to: date(self.to.as_ref())?,
+            horizon_days: self.horizon_days,
+            skip: self
+                .skip
+                .iter()
+                .map(|range| parse(range))
+                .collect::<Result<_>>()?,
+            skip_mode: self
+                .skip_mode
+                .as_deref()
+                .map(parse)
+                .transpose()?
+                .unwrap_or(BlackoutMode::Drop),
+            rotation: self
+                .rotation
+                .iter()
+                .map(|assignee| parse(assignee))
+                .collect::<Result<_>>()?,
+            rotation_mode: self
+                .rotation_mode
+                .as_deref()
+                .map(parse)
+                .transpose()?
+                .unwrap_or(RotationMode::Collection),
+            rotation_start: date(self.rotation_start.as_ref())?,
+            alarm: self
+                .alarms
+                .iter()
+                .map(|alarm| parse(alarm))
+                .collect::<Result<_>>()?,
+            time_of_day: self
+                .time_of_day
+                .as_deref()
+                .map(|time_of_day| parse_time_of_day(time_of_day).map_err(|error| anyhow!(error)))
+                .transpose()?,
+        })
+    }
+
+    /// Get the global options with the locale of this profile.
+    pub fn global_arguments(&self, global_arguments: &GlobalArguments) -> GlobalArguments {
+        GlobalArguments {
+            locale: self.locale.unwrap_or(global_arguments.locale),
+            ..global_arguments.clone()
+        }
+    }
+
+    /// Get the URL of the calendar of this profile on a server, e.g. to subscribe to it.
+    pub fn subscription_url(&self, server_url: &str) -> Result<Url> {
+        let mut url = Url::parse(server_url)
+            .with_context(|| format!("the server URL {server_url} is invalid"))?;
+        {
+            let mut path = url
+                .path_segments_mut()
+                .map_err(|_| anyhow!("the server URL {server_url} is invalid"))?;
+            path.pop_if_empty().push("calendar");
+            if let Some(provider) = &self.provider {
+                path.push(provider);
+            }
+        }
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("street", &self.street)
+                .append_pair("street_number", &self.street_number);
+            if let Some(district) = &self.district {
+                query.append_pair("district", district);
+            }
+            for waste_type in &self.exclude {
+                query.append_pair(&format!("exclude_{waste_type}"), "true");
+            }
+            if self.mark_shifted {
+                query.append_pair("mark_shifted", "true");
+            }
+            let pairs = [
+                (
+                    "predict_days",
+                    self.predict_days.map(|days| days.to_string()),
+                ),
+                ("from", self.from.map(|date| date.to_string())),
+                ("to", self.to.map(|date| date.to_string())),
+                (
+                    "horizon_days",
+                    self.horizon_days.map(|days| days.to_string()),
+                ),
+                ("skip", (!self.skip.is_empty()).then(|| self.skip.join(","))),
+                ("skip_mode", self.skip_mode.clone()),
+                (
+                    "rotation",
+                    (!self.rotation.is_empty()).then(|| self.rotation.join(",")),
+                ),
+                ("rotation_mode", self.rotation_mode.clone()),
+                (
+                    "rotation_start",
+                    self.rotation_start.map(|date| date.to_string()),
+                ),
+                (
+                    "alarm",
+                    (!self.alarms.is_empty()).then(|| self.alarms.join(",")),
+                ),
+                ("time_of_day", self.time_of_day.clone()),
+            ];
+            for (key, value) in pairs {
+                if let Some(value) = value {
+                    query.append_pair(key, &value);
+                }
+            }
+        }
+        Ok(url)
+    }
+
+    /// Get the path of the calendar file of this profile.
+    pub fn output(&self, name: &str) -> PathBuf {
+        self.output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("{name}.ics")))
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Parse a value of a profile like the command line does.
+fn parse<T: FromStr<Err = String>>(value: &str) -> Result<T> {
+    value.parse().map_err(|error: String| anyhow!(error))
+}
+
+/// Convert a TOML date, e.g. `2024-08-01`, to a date.
+fn date(value: Option<&Datetime>) -> Result<Option<NaiveDate>> {
+    value
+        .map(|value| {
+            NaiveDate::from_str(&value.to_string())
+                .with_context(|| format!("the value {value} is not a date"))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use chrono::{NaiveDate, NaiveTime};
+    use kgc_core::{garbage_client::WasteType, locale::Locale, source::SourceKind};
+
+    use crate::config::{Config, Profile};
+
+    fn config() -> Config {
+        Config::read(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src/config/tests/config.toml"))
+            .unwrap()
+    }
+
+    /// Test whether the profiles are read and converted to the options of `fetch`.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_read() {
+        let config = config();
+        assert_eq!(
+            config.profiles.keys().collect::<Vec<&String>>(),
+            vec!["home", "work"]
+        );
+        let home = config.profile("home").unwrap();
+        assert_eq!(home.exclude, vec![WasteType::Bulky, WasteType::Hazardous]);
+        assert_eq!(home.locale, Some(Locale::English));
+        assert_eq!(
+            home.output("home"),
+            PathBuf::from("/home/anna/calendars/home.ics")
+        );
+        let arguments = home.fetch_arguments().unwrap();
+        assert_eq!(arguments.schedule.street.as_deref(), Some("Kaiserstraße"));
+        assert_eq!(arguments.schedule.provider, "karlsruhe");
+        assert_eq!(arguments.schedule.look_back_days, 365);
+        assert!(arguments.exclude_bulky && arguments.exclude_hazardous);
+        assert!(!arguments.exclude_residual);
+        assert!(arguments.mark_shifted);
+        assert_eq!(arguments.skip.len(), 1);
+        assert_eq!(arguments.rotation.len(), 2);
+        assert_eq!(
+            arguments.rotation_start,
+            NaiveDate::from_ymd_opt(2024, 7, 1)
+        );
+        assert_eq!(arguments.alarm.len(), 2);
+        assert_eq!(arguments.time_of_day, NaiveTime::from_hms_opt(6, 0, 0));
+
+        let work = config.profile("work").unwrap();
+        assert_eq!(work.output("work"), PathBuf::from("work.ics"));
+        let arguments = work.schedule_arguments().unwrap();
+        assert_eq!(arguments.district.as_deref(), Some("Innenstadt-West"));
+        assert_eq!(
+            arguments.source,
+            vec![SourceKind::Website, SourceKind::Archive]
+        );
+    }
+
+    /// Test whether unknown keys, unknown profiles and invalid values are errors.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_read_errors() {
+        let profile = "[profile.home]\nstreet = \"Kaiserstraße\"\nstreet-number = \"1\"\n";
+        assert!(toml::from_str::<Config>(profile).is_ok());
+        assert!(toml::from_str::<Config>(&format!("{profile}colour = \"blue\"\n")).is_err());
+        assert!(toml::from_str::<Config>("[profile.home]\nstreet = \"Kaiserstraße\"\n").is_err());
+        let error = config().profile("holiday").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the profile \"holiday\" does not exist, the configured profiles are: home, work"
+        );
+        let profile = Profile {
+            alarms: vec![String::from("tomorrow")],
+            ..config().profile("home").unwrap().clone()
+        };
+        assert!(profile.fetch_arguments().is_err());
+        let profile = Profile {
+            skip_mode: Some(String::from("hide")),
+            ..config().profile("home").unwrap().clone()
+        };
+        assert!(profile.fetch_arguments().is_err());
+    }
+
+    /// Test whether the subscription URL carries the options of the profile as query parameters.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_subscription_url() {
+        let config = config();
+        let url = config
+            .profile("home")
+            .unwrap()
+            .subscription_url("https://kgc.example.org/")
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://kgc.example.org/calendar?street=Kaiserstra%C3%9Fe&street_number=1\
+            &exclude_bulky=true&exclude_hazardous=true&mark_shifted=true\
+            &skip=2024-08-01..2024-08-21&rotation=Anna%2CBen+%3Cben%40example.org%3E\
+            &rotation_start=2024-07-01&alarm=1d%2C6h&time_of_day=06%3A00"
+        );
+        let url = config
+            .profile("work")
+            .unwrap()
+            .subscription_url("https://kgc.example.org/kgc")
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://kgc.example.org/kgc/calendar/karlsruhe?street=Schlo%C3%9Fplatz&street_number=1\
+            &district=Innenstadt-West"
+        );
+        assert!(config
+            .profile("work")
+            .unwrap()
+            .subscription_url("kgc.example.org")
+            .is_err());
+    }
+}
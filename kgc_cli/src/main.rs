@@ -1,65 +1,88 @@
-use std::{env::current_dir, fs::write};
+use std::{path::PathBuf, process};
 
 use anyhow::Result;
-use clap::Parser;
-use kgc_core::{garbage_client, garbage_client::WasteTypeBitmask, ical::generator::Emitter};
+use clap::{Args, Parser, Subcommand};
+use kgc_core::locale::Locale;
 
+mod command;
+mod config;
+
+/// serve and fetch Karlsruhe's garbage collection dates as iCalendar files
 #[derive(Debug, Parser)]
-pub struct Arguments {
-    /// the street
-    pub street: String,
-    /// the street number
-    pub street_number: String,
-    /// exclude residual waste collection dates
-    #[arg(long)]
-    pub exclude_residual: bool,
-    /// exclude organic waste collection dates
-    #[arg(long)]
-    pub exclude_organic: bool,
-    /// exclude recyclable waste collection dates
-    #[arg(long)]
-    pub exclude_recyclable: bool,
-    /// exclude paper waste collection dates
-    #[arg(long)]
-    pub exclude_paper: bool,
-    /// exclude bulky waste collection dates
-    #[arg(long)]
-    pub exclude_bulky: bool,
+#[command(name = "kgc")]
+pub struct Cli {
+    #[command(flatten)]
+    pub global_arguments: GlobalArguments,
+    #[command(subcommand)]
+    pub command: Command,
 }
 
-impl From<&Arguments> for WasteTypeBitmask {
-    fn from(value: &Arguments) -> Self {
-        let mut waste_type_bitmask = WasteTypeBitmask::none();
-        if value.exclude_residual {
-            waste_type_bitmask |= WasteTypeBitmask::Residual;
-        }
-        if value.exclude_organic {
-            waste_type_bitmask |= WasteTypeBitmask::Organic;
-        }
-        if value.exclude_recyclable {
-            waste_type_bitmask |= WasteTypeBitmask::Recyclable;
-        }
-        if value.exclude_paper {
-            waste_type_bitmask |= WasteTypeBitmask::Paper;
-        }
-        if value.exclude_bulky {
-            waste_type_bitmask |= WasteTypeBitmask::Bulky;
-        }
-        waste_type_bitmask
-    }
+/// The options which are shared by all subcommands.
+#[derive(Debug, Clone, Args)]
+pub struct GlobalArguments {
+    /// the URL of the official website, e.g. of a mirror
+    #[arg(long, global = true)]
+    pub url: Option<String>,
+    /// the locale of the text output
+    #[arg(long, global = true, default_value_t = Locale::German)]
+    pub locale: Locale,
+    /// the directory of the archive which keeps past collection dates
+    #[arg(long, global = true, alias = "archive")]
+    pub cache: Option<PathBuf>,
+    /// the configuration file with the profiles, `kgc/config.toml` in the configuration directory by default
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// set up a profile of your address interactively
+    Init(command::init::Arguments),
+    /// serve the calendars over HTTP
+    Serve(command::serve::Arguments),
+    /// get the calendar and write it to the file `calendar.ics` in the current working directory, or to the output of a profile
+    #[command(alias = "cli")]
+    Fetch(Box<command::fetch::Arguments>),
+    /// write the calendars of many addresses from a CSV or JSON file
+    Batch(command::batch::Arguments),
+    /// fetch the calendars on a schedule and rewrite their files when they changed
+    Watch(Box<command::watch::Arguments>),
+    /// write the events of the calendars as objects of a CalDAV calendar collection and delete the stale ones
+    Sync(Box<command::sync::Arguments>),
+    /// print the upcoming collections
+    Next(Box<command::next::Arguments>),
+    /// check whether collections are due and run a command for each of them, exits with 1 if none are due
+    Remind(Box<command::remind::Arguments>),
+    /// compare two calendar files and print the changes of the collection schedule
+    Diff(command::diff::Arguments),
+    /// list the drop-off facilities, e.g. recycling centres, and their opening hours
+    Facilities(command::facilities::Arguments),
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Arguments::parse();
-    let calendar = garbage_client::get(
-        &args.street,
-        &args.street_number,
-        WasteTypeBitmask::from(&args),
-    )
-    .await?;
-    let mut path = current_dir()?;
-    path.push("calendar.ics");
-    write(path, calendar.generate())?;
-    Ok(())
+    let cli = Cli::parse();
+    let global_arguments = cli.global_arguments;
+    match cli.command {
+        Command::Init(args) => command::init::run(args, &global_arguments).await,
+        Command::Serve(args) => command::serve::run(args, &global_arguments).await,
+        Command::Fetch(args) => command::fetch::run(*args, &global_arguments).await,
+        Command::Batch(args) => command::batch::run(args, &global_arguments).await,
+        Command::Watch(args) => command::watch::run(*args, &global_arguments).await,
+        Command::Sync(args) => command::sync::run(*args, &global_arguments).await,
+        Command::Next(args) => command::next::run(*args, &global_arguments).await,
+        Command::Remind(args) => {
+            // scripts tell "nothing is due" (1) apart from a failure (2)
+            match command::remind::run(*args, &global_arguments).await {
+                Ok(true) => Ok(()),
+                Ok(false) => process::exit(1),
+                Err(error) => {
+                    eprintln!("Error: {error:?}");
+                    process::exit(2)
+                }
+            }
+        }
+        Command::Diff(args) => command::diff::run(args, &global_arguments),
+        Command::Facilities(args) => command::facilities::run(args),
+    }
 }
@@ -0,0 +1,414 @@
+//! This module normalizes addresses and matches them against the official street list.
+
+use std::{error::Error, fmt};
+
+use regex::Regex;
+
+/// An address consisting of a street, a house number and optionally its district.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    pub street: String,
+    pub house_number: HouseNumber,
+    /// The district, e.g. "Durlach", which is not needed for the official website but for district-based schedules.
+    pub district: Option<String>,
+    /// The street and the house number as they were requested, e.g. "Kaiserstr." and "12A".
+    ///
+    /// The UIDs of the events are built from them, so resolving the address does not change the UIDs of subscribers.
+    pub requested: (String, String),
+}
+
+impl Address {
+    /// Create an address from user input.
+    ///
+    /// The whitespace of the street is collapsed and the house number is parsed.
+    pub fn new(street: &str, house_number: &str) -> Result<Self, AddressError> {
+        let requested = (String::from(street), String::from(house_number));
+        let street = street.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if street.is_empty() {
+            return Err(AddressError::UnknownStreet(street));
+        }
+        Ok(Address {
+            street,
+            house_number: house_number.parse()?,
+            district: None,
+            requested,
+        })
+    }
+
+    /// Set the district of this address, empty districts are ignored.
+    pub fn with_district(mut self, district: Option<&str>) -> Self {
+        self.district = district
+            .map(str::trim)
+            .filter(|district| !district.is_empty())
+            .map(String::from);
+        self
+    }
+
+    /// Resolve the street against the official street list.
+    ///
+    /// Exact matches after normalization are preferred over prefix matches, which are preferred over fuzzy matches.
+    pub fn resolve(&self, streets: &[String]) -> Result<Self, AddressError> {
+        let key = normalize_street(&self.street);
+        let keyed_streets: Vec<(String, &String)> = streets
+            .iter()
+            .map(|street| (normalize_street(street), street))
+            .collect();
+        let exact_matches: Vec<&String> = keyed_streets
+            .iter()
+            .filter(|(street_key, _)| *street_key == key)
+            .map(|(_, street)| *street)
+            .collect();
+        let prefix_matches: Vec<&String> = keyed_streets
+            .iter()
+            .filter(|(street_key, _)| key.chars().count() >= 3 && street_key.starts_with(&key))
+            .map(|(_, street)| *street)
+            .collect();
+        let max_distance = (key.chars().count() / 4).max(1);
+        let best_distance = keyed_streets
+            .iter()
+            .map(|(street_key, _)| levenshtein(street_key, &key))
+            .min()
+            .filter(|distance| *distance <= max_distance);
+        let fuzzy_matches: Vec<&String> = keyed_streets
+            .iter()
+            .filter(|(street_key, _)| Some(levenshtein(street_key, &key)) == best_distance)
+            .map(|(_, street)| *street)
+            .collect();
+        let candidates = [exact_matches, prefix_matches, fuzzy_matches]
+            .into_iter()
+            .find(|matches| !matches.is_empty())
+            .ok_or_else(|| AddressError::UnknownStreet(self.street.clone()))?;
+        match candidates[..] {
+            [street] => Ok(Address {
+                street: street.clone(),
+                house_number: self.house_number.clone(),
+                district: self.district.clone(),
+                requested: self.requested.clone(),
+            }),
+            _ => Err(AddressError::AmbiguousStreet {
+                street: self.street.clone(),
+                candidates: candidates.into_iter().cloned().collect(),
+            }),
+        }
+    }
+
+    /// Check whether this address is the same as the given street and house number after normalization.
+    ///
+    /// Ranges match their first house number, because only that one is sent to the official server.
+    pub fn matches(&self, street: &str, house_number: &str) -> bool {
+        self.matches_street(street)
+            && house_number
+                .parse::<HouseNumber>()
+                .is_ok_and(|house_number| house_number.upstream() == self.house_number.upstream())
+    }
+
+    /// Check whether the street of this address is the same as the given street after normalization.
+    pub fn matches_street(&self, street: &str) -> bool {
+        normalize_street(street) == normalize_street(&self.street)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.street, self.house_number)
+    }
+}
+
+/// A house number with an optional suffix (e.g. "12a") or an optional range end (e.g. "3-5").
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HouseNumber {
+    pub number: u32,
+    pub suffix: Option<char>,
+    pub range_end: Option<u32>,
+}
+
+impl HouseNumber {
+    /// Get the house number which is sent to the official server.
+    ///
+    /// Ranges are represented by their first house number.
+    pub fn upstream(&self) -> String {
+        let number = self.number;
+        match self.suffix {
+            Some(suffix) => format!("{number}{suffix}"),
+            None => format!("{number}"),
+        }
+    }
+}
+
+impl std::str::FromStr for HouseNumber {
+    type Err = AddressError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let house_number_regex = Regex::new(
+            r"(?x)
+                ^\s*
+                (?P<number>\d+) # the house number
+                \s*
+                (?P<suffix>[[:alpha:]])? # the optional suffix
+                (?:\s*[-/]\s*(?P<range_end>\d+))? # the optional end of a range
+                \s*$
+            ",
+        )
+        .unwrap();
+        let invalid = || AddressError::InvalidHouseNumber(value.to_string());
+        let captures = house_number_regex.captures(value).ok_or_else(invalid)?;
+        let number: u32 = captures["number"].parse().map_err(|_| invalid())?;
+        let range_end: Option<u32> = captures
+            .name("range_end")
+            .map(|range_end| range_end.as_str().parse())
+            .transpose()
+            .map_err(|_| invalid())?;
+        if number == 0 || range_end.is_some_and(|range_end| range_end <= number) {
+            return Err(invalid());
+        }
+        Ok(HouseNumber {
+            number,
+            suffix: captures
+                .name("suffix")
+                .and_then(|suffix| suffix.as_str().to_lowercase().chars().next()),
+            range_end,
+        })
+    }
+}
+
+impl fmt::Display for HouseNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.upstream())?;
+        if let Some(range_end) = self.range_end {
+            write!(f, "-{range_end}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The address is invalid or can not be matched unambiguously against the official street list.
+#[derive(Debug, PartialEq)]
+pub enum AddressError {
+    InvalidHouseNumber(String),
+    UnknownStreet(String),
+    AmbiguousStreet {
+        street: String,
+        candidates: Vec<String>,
+    },
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::InvalidHouseNumber(house_number) => {
+                write!(f, "the house number \"{house_number}\" is invalid")
+            }
+            AddressError::UnknownStreet(street) => write!(f, "the street \"{street}\" is unknown"),
+            AddressError::AmbiguousStreet { street, candidates } => write!(
+                f,
+                "the street \"{street}\" is ambiguous, candidates are: {}",
+                candidates.join(", ")
+            ),
+        }
+    }
+}
+
+impl Error for AddressError {}
+
+/// Suggest the streets of the official street list which contain the given input after normalization, e.g. while it is typed.
+///
+/// Streets which start with the input are suggested first.
+pub fn suggest_streets<'a>(streets: &'a [String], input: &str, limit: usize) -> Vec<&'a String> {
+    let key = normalize_street(input);
+    let mut suggestions: Vec<(bool, &String)> = streets
+        .iter()
+        .filter_map(|street| {
+            let street_key = normalize_street(street);
+            street_key
+                .contains(&key)
+                .then(|| (!street_key.starts_with(&key), street))
+        })
+        .collect();
+    suggestions.sort();
+    suggestions
+        .into_iter()
+        .take(limit)
+        .map(|(_, street)| street)
+        .collect()
+}
+
+/// Normalize a street for comparison.
+///
+/// Case, "ß"/"ss", umlauts, the abbreviation "str." and all separators are normalized.
+pub(crate) fn normalize_street(street: &str) -> String {
+    let abbreviation_regex = Regex::new(r"str(\.|$)").unwrap();
+    let street = street
+        .to_lowercase()
+        .replace('ß', "ss")
+        .replace('ä', "ae")
+        .replace('ö', "oe")
+        .replace('ü', "ue");
+    abbreviation_regex
+        .replace_all(&street, "strasse")
+        .chars()
+        .filter(|char| char.is_alphanumeric())
+        .collect()
+}
+
+/// Calculate the Levenshtein distance between two strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::{
+        levenshtein, normalize_street, suggest_streets, Address, AddressError, HouseNumber,
+    };
+
+    fn get_test_streets() -> Vec<String> {
+        [
+            "Kaiserallee",
+            "Kaiserpassage",
+            "Kaiserslauterner Straße",
+            "Kaiserstraße",
+            "Rüppurrer Straße",
+            "Schloßplatz",
+            "Schlossbergstraße",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    #[test]
+    fn test_normalize_street() {
+        assert_eq!(normalize_street("Kaiserstr."), "kaiserstrasse");
+        assert_eq!(normalize_street("kaiserstrasse"), "kaiserstrasse");
+        assert_eq!(normalize_street("Kaiser  Straße"), "kaiserstrasse");
+        assert_eq!(normalize_street("Kaiserstr"), "kaiserstrasse");
+        assert_eq!(normalize_street("Rüppurrer Str. "), "rueppurrerstrasse");
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("schlossplatz", "schlosplatz"), 1);
+    }
+
+    #[test]
+    fn test_parse_house_number() {
+        assert_eq!(
+            "12".parse(),
+            Ok(HouseNumber {
+                number: 12,
+                suffix: None,
+                range_end: None,
+            })
+        );
+        assert_eq!(
+            " 12 A".parse(),
+            Ok(HouseNumber {
+                number: 12,
+                suffix: Some('a'),
+                range_end: None,
+            })
+        );
+        assert_eq!(
+            "3 - 5".parse(),
+            Ok(HouseNumber {
+                number: 3,
+                suffix: None,
+                range_end: Some(5),
+            })
+        );
+        assert_eq!(
+            "5-3".parse::<HouseNumber>(),
+            Err(AddressError::InvalidHouseNumber(String::from("5-3")))
+        );
+        assert_eq!(
+            "a".parse::<HouseNumber>(),
+            Err(AddressError::InvalidHouseNumber(String::from("a")))
+        );
+        assert_eq!("12 A".parse::<HouseNumber>().unwrap().to_string(), "12a");
+        assert_eq!("3 - 5".parse::<HouseNumber>().unwrap().to_string(), "3-5");
+        assert_eq!("3 - 5".parse::<HouseNumber>().unwrap().upstream(), "3");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let streets = get_test_streets();
+        for street in [
+            "Kaiserstr.",
+            "kaiserstrasse",
+            "Kaiser Straße",
+            "Kaiserstrase",
+        ] {
+            let address = Address::new(street, "1").unwrap().resolve(&streets);
+            assert_eq!(address.unwrap().street, "Kaiserstraße");
+        }
+        let address = Address::new("Kaiserstr.", "12A").unwrap().resolve(&streets);
+        assert_eq!(
+            address.unwrap().requested,
+            (String::from("Kaiserstr."), String::from("12A"))
+        );
+        let address = Address::new("Rueppurrer Str", "1")
+            .unwrap()
+            .resolve(&streets);
+        assert_eq!(address.unwrap().street, "Rüppurrer Straße");
+        let address = Address::new("Schlossplatz", "1").unwrap().resolve(&streets);
+        assert_eq!(address.unwrap().street, "Schloßplatz");
+        let address = Address::new("Kaiser", "1").unwrap().resolve(&streets);
+        assert_eq!(
+            address,
+            Err(AddressError::AmbiguousStreet {
+                street: String::from("Kaiser"),
+                candidates: vec![
+                    String::from("Kaiserallee"),
+                    String::from("Kaiserpassage"),
+                    String::from("Kaiserslauterner Straße"),
+                    String::from("Kaiserstraße"),
+                ],
+            })
+        );
+        let address = Address::new("Nowhere", "1").unwrap().resolve(&streets);
+        assert_eq!(
+            address,
+            Err(AddressError::UnknownStreet(String::from("Nowhere")))
+        );
+    }
+
+    #[test]
+    fn test_suggest_streets() {
+        let streets = get_test_streets();
+        assert_eq!(
+            suggest_streets(&streets, "kaiser", 3),
+            vec!["Kaiserallee", "Kaiserpassage", "Kaiserslauterner Straße"]
+        );
+        assert_eq!(
+            suggest_streets(&streets, "Schlossp", 10),
+            vec!["Schloßplatz"]
+        );
+        assert_eq!(
+            suggest_streets(&streets, "lauterner", 10),
+            vec!["Kaiserslauterner Straße"]
+        );
+        assert!(suggest_streets(&streets, "Nowhere", 10).is_empty());
+    }
+
+    #[test]
+    fn test_matches() {
+        let address = Address::new("Schloßplatz", "12a").unwrap();
+        assert!(address.matches("Schlossplatz", "12 A"));
+        assert!(!address.matches("Schlossplatz", "12"));
+        assert!(!address.matches("Kaiserstraße", "12a"));
+        let address = Address::new("Schloßplatz", "3-5").unwrap();
+        assert!(address.matches("Schloßplatz", "3"));
+    }
+}
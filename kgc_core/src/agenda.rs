@@ -0,0 +1,158 @@
+//! This module lists the upcoming collections of waste data as an agenda, e.g. for the terminal.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::Serialize;
+
+use crate::{
+    garbage_client::{WasteData, WasteType},
+    locale::Locale,
+};
+
+/// An upcoming collection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Entry {
+    pub date: NaiveDate,
+    pub weekday: Weekday,
+    pub days_until: i64,
+    pub waste_type: WasteType,
+    /// Why the collection has been shifted, or the time window and location of a stop.
+    pub note: Option<String>,
+    /// Whether the date is predicted instead of published.
+    pub predicted: bool,
+}
+
+impl Entry {
+    /// Describe the number of days until the collection, e.g. "morgen" or "in 3 Tagen".
+    pub fn describe_days_until(&self, locale: Locale) -> String {
+        match (locale, self.days_until) {
+            (Locale::German, 0) => String::from("heute"),
+            (Locale::German, 1) => String::from("morgen"),
+            (Locale::German, days) => format!("in {days} Tagen"),
+            (Locale::English, 0) => String::from("today"),
+            (Locale::English, 1) => String::from("tomorrow"),
+            (Locale::English, days) => format!("in {days} days"),
+        }
+    }
+
+    /// Render a message from a template.
+    ///
+    /// The placeholders `{type}`, `{date}`, `{weekday}`, `{when}` and `{note}` are replaced.
+    pub fn render(&self, template: &str, locale: Locale) -> String {
+        template
+            .replace("{type}", locale.waste_type(self.waste_type))
+            .replace("{date}", &locale.format_date(self.date))
+            .replace("{weekday}", locale.weekday(self.weekday))
+            .replace("{when}", &self.describe_days_until(locale))
+            .replace("{note}", self.note.as_deref().unwrap_or_default())
+    }
+}
+
+/// Get the collections from today until a number of days from today, ordered by date.
+///
+/// Only the given waste types are listed, or all of them if none are given.
+pub fn upcoming(
+    waste_data: &WasteData,
+    today: NaiveDate,
+    days: i64,
+    waste_types: &[WasteType],
+    locale: Locale,
+) -> Vec<Entry> {
+    let until = today + Duration::days(days);
+    let entry = |waste_type: WasteType, date: NaiveDate, note: Option<String>, predicted| Entry {
+        date,
+        weekday: date.weekday(),
+        days_until: (date - today).num_days(),
+        waste_type,
+        note,
+        predicted,
+    };
+    let mut entries: Vec<Entry> = WasteType::ALL
+        .into_iter()
+        .filter(|waste_type| waste_types.is_empty() || waste_types.contains(waste_type))
+        .flat_map(|waste_type| {
+            let published = waste_data
+                .collections(waste_type)
+                .into_iter()
+                .map(move |collection| {
+                    let stop = waste_data
+                        .stops
+                        .iter()
+                        .filter(|stop| {
+                            waste_type == WasteType::Hazardous && stop.date == collection.date
+                        })
+                        .map(|stop| stop.describe())
+                        .collect::<Vec<String>>();
+                    let note = collection
+                        .shift_description(locale)
+                        .or_else(|| (!stop.is_empty()).then(|| stop.join("; ")));
+                    entry(waste_type, collection.date, note, false)
+                });
+            let predicted = waste_data
+                .predictions
+                .get(&waste_type)
+                .into_iter()
+                .flatten()
+                .map(move |date| entry(waste_type, *date, None, true));
+            published.chain(predicted).collect::<Vec<Entry>>()
+        })
+        .filter(|entry| today <= entry.date && entry.date <= until)
+        .collect();
+    entries.sort_by_key(|entry| (entry.date, entry.waste_type));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, NaiveDate, Weekday};
+
+    use crate::{
+        agenda::upcoming,
+        garbage_client::{tests::get_test_waste_data, WasteType},
+        locale::Locale,
+    };
+
+    #[test]
+    fn test_upcoming() {
+        let mut waste_data = get_test_waste_data();
+        waste_data.predictions.insert(
+            WasteType::Organic,
+            vec![NaiveDate::from_ymd_opt(2023, 6, 28).unwrap()],
+        );
+        let today = NaiveDate::from_ymd_opt(2023, 6, 21).unwrap();
+        let entries = upcoming(&waste_data, today, 8, &[], Locale::English);
+        let summary: Vec<(u32, WasteType, bool)> = entries
+            .iter()
+            .map(|entry| (entry.date.day(), entry.waste_type, entry.predicted))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (21, WasteType::Organic, false),
+                (22, WasteType::Recyclable, false),
+                (28, WasteType::Organic, true),
+                (29, WasteType::Residual, false),
+            ]
+        );
+        assert_eq!(entries[0].describe_days_until(Locale::German), "heute");
+        assert_eq!(
+            entries[1].render("{type}: {when} ({weekday}, {date})", Locale::German),
+            "Wertstoff: morgen (Donnerstag, 22.06.2023)"
+        );
+        assert_eq!(entries[1].describe_days_until(Locale::English), "tomorrow");
+        assert_eq!(entries[3].weekday, Weekday::Thu);
+        assert_eq!(
+            entries[3].note.as_deref(),
+            Some("Shifted from Friday, 2023-06-30: Aus betrieblichen Gründen nicht im Einsatz am 30.06. wurde berücksichtigt")
+        );
+
+        let entries = upcoming(
+            &waste_data,
+            today,
+            8,
+            &[WasteType::Residual],
+            Locale::German,
+        );
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].describe_days_until(Locale::German), "in 8 Tagen");
+    }
+}
@@ -0,0 +1,509 @@
+//! This archive persists the fetched waste data, so past collections do not disappear from the calendar.
+//!
+//! The official website only shows the next few dates per waste type.
+//! Every fetch is merged into a growing history which is stored as one JSON file per address.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use chrono::{Duration, Local, NaiveDate};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    address::{normalize_street, Address},
+    diff::MAX_MOVE_DAYS,
+    file::write_atomically,
+    garbage_client::{WasteData, WasteType},
+    source::ScheduleSource,
+};
+
+/// A persistent archive of waste data histories.
+#[derive(Debug)]
+pub struct Archive {
+    directory: PathBuf,
+    look_back_days: i64,
+    lock: Mutex<()>,
+}
+
+impl Archive {
+    /// Create an archive in a directory.
+    ///
+    /// Past collections are only rendered if they are at most `look_back_days` in the past.
+    pub fn new(directory: impl Into<PathBuf>, look_back_days: i64) -> Self {
+        Archive {
+            directory: directory.into(),
+            look_back_days,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Merge fetched waste data into the history of an address.
+    ///
+    /// The waste data of the history within the look-back window is returned.
+    pub fn merge(
+        &self,
+        address: &Address,
+        waste_data: WasteData,
+        today: NaiveDate,
+    ) -> Result<WasteData> {
+        let _guard = self.lock.lock().unwrap();
+        let mut history = self.read(address)?;
+        history.merge(&waste_data, today);
+        history.write(&self.path(address))?;
+        let mut merged_waste_data = history.waste_data(today - Duration::days(self.look_back_days));
+        merged_waste_data.notices = waste_data.notices;
+        merged_waste_data.rhythms = waste_data.rhythms;
+        merged_waste_data.notes = waste_data.notes;
+        merged_waste_data.stops = waste_data.stops;
+        merged_waste_data.warnings = waste_data.warnings;
+        Ok(merged_waste_data)
+    }
+
+    /// Get the waste data of the history of an address within the look-back window without fetching.
+    pub fn waste_data(&self, address: &Address, today: NaiveDate) -> Result<WasteData> {
+        let history = self.history(address)?;
+        Ok(history.waste_data(today - Duration::days(self.look_back_days)))
+    }
+
+    /// Get the history of an address without fetching.
+    pub fn history(&self, address: &Address) -> Result<History> {
+        let _guard = self.lock.lock().unwrap();
+        self.read(address)
+    }
+
+    /// Read the history of an address, falling back to the file name of earlier versions.
+    fn read(&self, address: &Address) -> Result<History> {
+        let path = self.path(address);
+        match path.exists() {
+            true => History::read(&path),
+            false => History::read(&self.legacy_path(address)),
+        }
+    }
+
+    /// Get the path of the history of an address, e.g. `rueppurrerstrasse-12a.json`.
+    ///
+    /// The street is normalized, so the history written under the resolved address is found
+    /// under any spelling of it, e.g. "Rüppurrer Str." while the official website is down.
+    fn path(&self, address: &Address) -> PathBuf {
+        let separator_regex = Regex::new(r"[^\p{Alphabetic}\p{N}]+").unwrap();
+        let house_number = address.house_number.to_string();
+        let house_number = separator_regex.replace_all(&house_number, "-");
+        self.directory.join(format!(
+            "{}-{house_number}.json",
+            normalize_street(&address.street)
+        ))
+    }
+
+    /// Get the path of the history of an address of earlier versions, e.g. `Rüppurrer-Straße-12a.json`.
+    fn legacy_path(&self, address: &Address) -> PathBuf {
+        let separator_regex = Regex::new(r"[^\p{Alphabetic}\p{N}]+").unwrap();
+        let address = address.to_string();
+        let file_stem = separator_regex.replace_all(&address, "-");
+        self.directory.join(format!("{file_stem}.json"))
+    }
+}
+
+/// A source whose fetched waste data is merged into the history of an archive.
+#[derive(Debug)]
+pub struct ArchivingSource {
+    pub source: Box<dyn ScheduleSource>,
+    pub archive: Arc<Archive>,
+}
+
+#[async_trait]
+impl ScheduleSource for ArchivingSource {
+    /// Fetch the waste data from the source and merge it into the full history.
+    ///
+    /// The waste data of the history is returned, so past collections within the look-back window are kept.
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+        let (address, waste_data) = self.source.fetch(address).await?;
+        let waste_data = self
+            .archive
+            .merge(&address, waste_data, Local::now().date_naive())?;
+        Ok((address, waste_data))
+    }
+}
+
+#[async_trait]
+impl ScheduleSource for Archive {
+    /// Get the waste data of the history of an address without fetching.
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+        if self.history(address)?.entries.is_empty() {
+            bail!("the archive does not contain the address \"{address}\"");
+        }
+        let waste_data = self.waste_data(address, Local::now().date_naive())?;
+        Ok((address.clone(), waste_data))
+    }
+}
+
+/// The history of collection dates of a single address.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    pub entries: Vec<HistoryEntry>,
+}
+
+/// A collection date which has been seen at least once.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub waste_type: WasteType,
+    pub date: NaiveDate,
+    pub first_seen: NaiveDate,
+    pub last_seen: NaiveDate,
+    /// The date on which this collection vanished from the official website before it took place.
+    pub withdrawn: Option<NaiveDate>,
+    /// The date to which this collection moved, if it was withdrawn in favour of a nearby date.
+    pub moved_to: Option<NaiveDate>,
+}
+
+impl History {
+    fn read(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(History::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Write this history atomically, so a crash or a concurrent request never leaves a truncated file behind.
+    fn write(&self, path: &Path) -> Result<()> {
+        write_atomically(path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+
+    /// Merge fetched waste data into this history.
+    ///
+    /// Future dates within the fetched horizon which are not fetched anymore are withdrawn.
+    /// A withdrawn date has moved if a new date of the same waste type appeared nearby.
+    pub fn merge(&mut self, waste_data: &WasteData, today: NaiveDate) {
+        for waste_type in WasteType::ALL {
+            let dates = waste_data.dates(waste_type);
+            for date in dates {
+                match self.entry_mut(waste_type, *date) {
+                    Some(entry) => {
+                        entry.last_seen = today;
+                        entry.withdrawn = None;
+                        entry.moved_to = None;
+                    }
+                    None => self.entries.push(HistoryEntry {
+                        waste_type,
+                        date: *date,
+                        first_seen: today,
+                        last_seen: today,
+                        withdrawn: None,
+                        moved_to: None,
+                    }),
+                }
+            }
+            let Some(horizon) = dates.iter().max() else {
+                continue;
+            };
+            let new_dates: Vec<NaiveDate> = self
+                .entries
+                .iter()
+                .filter(|entry| entry.waste_type == waste_type && entry.first_seen == today)
+                .map(|entry| entry.date)
+                .collect();
+            for entry in self.entries.iter_mut().filter(|entry| {
+                entry.waste_type == waste_type
+                    && entry.withdrawn.is_none()
+                    && entry.date >= today
+                    && entry.date <= *horizon
+                    && !dates.contains(&entry.date)
+            }) {
+                entry.withdrawn = Some(today);
+                entry.moved_to = new_dates
+                    .iter()
+                    .filter(|date| (**date - entry.date).num_days().abs() <= MAX_MOVE_DAYS)
+                    .min_by_key(|date| (**date - entry.date).num_days().abs())
+                    .copied();
+            }
+        }
+        self.entries
+            .sort_by_key(|entry| (entry.waste_type, entry.date));
+    }
+
+    /// Get the waste data of all dates since a specific date which have not been withdrawn.
+    pub fn waste_data(&self, since: NaiveDate) -> WasteData {
+        let mut waste_data = WasteData::default();
+        for entry in &self.entries {
+            if entry.withdrawn.is_none() && entry.date >= since {
+                waste_data.dates_mut(entry.waste_type).push(entry.date);
+            }
+        }
+        waste_data
+    }
+
+    fn entry_mut(&mut self, waste_type: WasteType, date: NaiveDate) -> Option<&mut HistoryEntry> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.waste_type == waste_type && entry.date == date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs, path::Path, sync::Arc};
+
+    use chrono::{Duration, Local, NaiveDate};
+
+    use crate::{
+        address::Address,
+        archive::{Archive, History, HistoryEntry},
+        garbage_client::{tests::date, WasteData, WasteType},
+        provider::karlsruhe::KarlsruheProvider,
+        source::{ScheduleSource, SourceConfig, SourceKind},
+    };
+
+    #[test]
+    fn test_merge() {
+        let mut history = History::default();
+        let waste_data = WasteData {
+            organic_waste: vec![date(6, 7), date(6, 14), date(6, 21)],
+            ..WasteData::default()
+        };
+        history.merge(&waste_data, date(6, 1));
+        assert_eq!(history.entries.len(), 3);
+
+        let waste_data = WasteData {
+            organic_waste: vec![date(6, 13), date(6, 21), date(6, 28)],
+            ..WasteData::default()
+        };
+        history.merge(&waste_data, date(6, 8));
+        assert_eq!(
+            history.entries,
+            vec![
+                HistoryEntry {
+                    waste_type: WasteType::Organic,
+                    date: date(6, 7),
+                    first_seen: date(6, 1),
+                    last_seen: date(6, 1),
+                    withdrawn: None,
+                    moved_to: None,
+                },
+                HistoryEntry {
+                    waste_type: WasteType::Organic,
+                    date: date(6, 13),
+                    first_seen: date(6, 8),
+                    last_seen: date(6, 8),
+                    withdrawn: None,
+                    moved_to: None,
+                },
+                HistoryEntry {
+                    waste_type: WasteType::Organic,
+                    date: date(6, 14),
+                    first_seen: date(6, 1),
+                    last_seen: date(6, 1),
+                    withdrawn: Some(date(6, 8)),
+                    moved_to: Some(date(6, 13)),
+                },
+                HistoryEntry {
+                    waste_type: WasteType::Organic,
+                    date: date(6, 21),
+                    first_seen: date(6, 1),
+                    last_seen: date(6, 8),
+                    withdrawn: None,
+                    moved_to: None,
+                },
+                HistoryEntry {
+                    waste_type: WasteType::Organic,
+                    date: date(6, 28),
+                    first_seen: date(6, 8),
+                    last_seen: date(6, 8),
+                    withdrawn: None,
+                    moved_to: None,
+                },
+            ]
+        );
+    }
+
+    /// Test whether the fetched waste data is archived and the history is returned at its position in the chain.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_chain() {
+        let directory = temp_dir().join("kgc_test_chain");
+        let _ = fs::remove_dir_all(&directory);
+        let static_dataset = directory.join("static.json");
+        fs::create_dir_all(&directory).unwrap();
+        let today = Local::now().date_naive();
+        let write_static_dataset = |date: NaiveDate| {
+            let entries = format!(
+                r#"[{{"street": "Schloßplatz", "street_number": "1", "organic_waste": ["{date}"]}}]"#
+            );
+            fs::write(&static_dataset, entries).unwrap();
+        };
+        let config = SourceConfig {
+            html_file: Some(directory.join("missing.html")),
+            static_dataset: Some(static_dataset.clone()),
+            archive: Some(Arc::new(Archive::new(&directory, 365))),
+            ..SourceConfig::default()
+        };
+        let provider = Arc::new(KarlsruheProvider::default());
+        let address = Address::new("Schloßplatz", "1").unwrap();
+        let chain = |kinds: &[SourceKind]| config.chain(provider.clone(), kinds).unwrap();
+        assert!(chain(&[SourceKind::Archive]).fetch(&address).await.is_err());
+
+        write_static_dataset(today);
+        let (_, waste_data) = chain(&[SourceKind::Static]).fetch(&address).await.unwrap();
+        assert_eq!(waste_data.organic_waste, vec![today]);
+
+        let tomorrow = today + Duration::days(1);
+        write_static_dataset(tomorrow);
+        let (_, waste_data) = chain(&[SourceKind::Archive, SourceKind::Static])
+            .fetch(&address)
+            .await
+            .unwrap();
+        assert_eq!(waste_data.organic_waste, vec![today]);
+
+        let (_, waste_data) = chain(&[SourceKind::Html]).fetch(&address).await.unwrap();
+        assert_eq!(waste_data.organic_waste, vec![today]);
+    }
+
+    /// Test whether the archived history is the last resort of a chain whose website is down, under any spelling of the address.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_chain_website_down() {
+        let directory = temp_dir().join("kgc_test_chain_website_down");
+        let _ = fs::remove_dir_all(&directory);
+        let static_dataset = directory.join("static.json");
+        fs::create_dir_all(&directory).unwrap();
+        let today = Local::now().date_naive();
+        fs::write(
+            &static_dataset,
+            format!(
+                r#"[{{"street": "Schloßplatz", "street_number": "1", "paper_waste": ["{today}"]}}]"#
+            ),
+        )
+        .unwrap();
+        let config = SourceConfig {
+            static_dataset: Some(static_dataset),
+            archive: Some(Arc::new(Archive::new(&directory, 365))),
+            ..SourceConfig::default()
+        };
+        let provider = Arc::new(KarlsruheProvider::new("http://127.0.0.1:1/"));
+        let chain = |kinds: &[SourceKind]| config.chain(provider.clone(), kinds).unwrap();
+        let address = Address::new("Schlossplatz", "1").unwrap();
+        assert!(chain(&[SourceKind::Website]).fetch(&address).await.is_err());
+
+        chain(&[SourceKind::Static])
+            .fetch(&Address::new("Schloßplatz", "1").unwrap())
+            .await
+            .unwrap();
+        let (_, waste_data) = chain(&[SourceKind::Website]).fetch(&address).await.unwrap();
+        assert_eq!(waste_data.paper_waste, vec![today]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_path() {
+        let archive = Archive::new("archive", 365);
+        let path =
+            |street, house_number| archive.path(&Address::new(street, house_number).unwrap());
+        assert_eq!(
+            path("Schloßplatz", "1"),
+            Path::new("archive/schlossplatz-1.json")
+        );
+        assert_eq!(path("Schlossplatz", "1"), path("Schloßplatz", "1"));
+        assert_eq!(
+            path("Rüppurrer Straße", "12 A"),
+            Path::new("archive/rueppurrerstrasse-12a.json")
+        );
+        assert_eq!(
+            path("Rüppurrer Str.", "12a"),
+            path("Rüppurrer Straße", "12 A")
+        );
+        assert_eq!(
+            path("Kaiserstraße", "3-5"),
+            Path::new("archive/kaiserstrasse-3-5.json")
+        );
+    }
+
+    /// Test whether the history written under the resolved address is read under another spelling of it.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_fetch_spelling() {
+        let directory = temp_dir().join("kgc_test_archive_spelling");
+        let _ = fs::remove_dir_all(&directory);
+        let archive = Archive::new(&directory, 365);
+        let today = Local::now().date_naive();
+        let waste_data = WasteData {
+            paper_waste: vec![today],
+            ..WasteData::default()
+        };
+        let resolved = Address::new("Kaiserstraße", "12").unwrap();
+        archive.merge(&resolved, waste_data, today).unwrap();
+        for (street, house_number) in [("Kaiserstr.", "12"), ("kaiserstrasse", "12")] {
+            let address = Address::new(street, house_number).unwrap();
+            let (_, waste_data) = archive.fetch(&address).await.unwrap();
+            assert_eq!(waste_data.paper_waste, vec![today]);
+        }
+        assert!(archive
+            .fetch(&Address::new("Kaiserstr.", "14").unwrap())
+            .await
+            .is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    /// Test whether the history of earlier versions is still read and then written under the new file name.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_legacy_path() {
+        let directory = temp_dir().join("kgc_test_archive_legacy_path");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let archive = Archive::new(&directory, 365);
+        let today = Local::now().date_naive();
+        let address = Address::new("Schloßplatz", "1").unwrap();
+        let history = History {
+            entries: vec![HistoryEntry {
+                waste_type: WasteType::Paper,
+                date: today,
+                first_seen: today,
+                last_seen: today,
+                withdrawn: None,
+                moved_to: None,
+            }],
+        };
+        history
+            .write(&directory.join("Schloßplatz-1.json"))
+            .unwrap();
+        assert_eq!(archive.history(&address).unwrap(), history);
+        archive
+            .merge(&address, WasteData::default(), today)
+            .unwrap();
+        assert!(directory.join("schlossplatz-1.json").exists());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_waste_data() {
+        let mut history = History::default();
+        let waste_data = WasteData {
+            residual_waste: vec![date(6, 16), date(6, 29)],
+            organic_waste: vec![date(6, 7), date(6, 14)],
+            ..WasteData::default()
+        };
+        history.merge(&waste_data, date(6, 1));
+        let waste_data = WasteData {
+            residual_waste: vec![date(6, 29), date(7, 14)],
+            organic_waste: vec![date(6, 21)],
+            ..WasteData::default()
+        };
+        history.merge(&waste_data, date(6, 20));
+        assert_eq!(
+            history.waste_data(date(6, 10)),
+            WasteData {
+                residual_waste: vec![date(6, 16), date(6, 29), date(7, 14)],
+                organic_waste: vec![date(6, 14), date(6, 21)],
+                ..WasteData::default()
+            }
+        );
+    }
+}
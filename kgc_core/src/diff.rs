@@ -0,0 +1,225 @@
+//! This module compares two snapshots of waste data, e.g. two fetches or two generated iCalendar files.
+
+use std::collections::BTreeSet;
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::{
+    garbage_client::{WasteData, WasteType},
+    locale::Locale,
+};
+
+/// The maximum number of days a collection is moved because of e.g. a holiday.
+pub(crate) static MAX_MOVE_DAYS: i64 = 7;
+
+/// A change of the collection schedule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    Added {
+        waste_type: WasteType,
+        date: NaiveDate,
+    },
+    Removed {
+        waste_type: WasteType,
+        date: NaiveDate,
+    },
+    Moved {
+        waste_type: WasteType,
+        from: NaiveDate,
+        to: NaiveDate,
+    },
+    NewWasteType {
+        waste_type: WasteType,
+        dates: Vec<NaiveDate>,
+    },
+}
+
+impl Change {
+    /// Describe this change in a human-readable way.
+    pub fn describe(&self, locale: Locale) -> String {
+        let date = |date: &NaiveDate| locale.format_date(*date);
+        match self {
+            Change::Added {
+                waste_type,
+                date: added,
+            } => {
+                let waste_type = locale.waste_type(*waste_type);
+                let added = date(added);
+                match locale {
+                    Locale::German => format!("{waste_type}: neuer Termin am {added}"),
+                    Locale::English => format!("{waste_type}: new collection on {added}"),
+                }
+            }
+            Change::Removed {
+                waste_type,
+                date: removed,
+            } => {
+                let waste_type = locale.waste_type(*waste_type);
+                let removed = date(removed);
+                match locale {
+                    Locale::German => format!("{waste_type}: Termin am {removed} entfällt"),
+                    Locale::English => {
+                        format!("{waste_type}: collection on {removed} was cancelled")
+                    }
+                }
+            }
+            Change::Moved {
+                waste_type,
+                from,
+                to,
+            } => {
+                let waste_type = locale.waste_type(*waste_type);
+                let (from, to) = (date(from), date(to));
+                match locale {
+                    Locale::German => {
+                        format!("{waste_type}: Termin vom {from} auf den {to} verschoben")
+                    }
+                    Locale::English => {
+                        format!("{waste_type}: collection moved from {from} to {to}")
+                    }
+                }
+            }
+            Change::NewWasteType { waste_type, dates } => {
+                let waste_type = locale.waste_type(*waste_type);
+                let dates = dates.iter().map(date).collect::<Vec<String>>().join(", ");
+                match locale {
+                    Locale::German => {
+                        format!("Neue Abfallart {waste_type} mit den Terminen {dates}")
+                    }
+                    Locale::English => {
+                        format!("New waste type {waste_type} with the collections {dates}")
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Compare two snapshots of waste data.
+///
+/// Only the period covered by both snapshots is compared per waste type,
+/// so dates which have passed or which were published in the meantime are not reported.
+/// A removed date and an added date of the same waste type at most a week apart are reported as a move.
+pub fn diff(old: &WasteData, new: &WasteData) -> Vec<Change> {
+    let mut changes = vec![];
+    for waste_type in WasteType::ALL {
+        let old_dates: BTreeSet<NaiveDate> = old.dates(waste_type).iter().copied().collect();
+        let new_dates: BTreeSet<NaiveDate> = new.dates(waste_type).iter().copied().collect();
+        let (Some(old_first), Some(old_last)) = (old_dates.first(), old_dates.last()) else {
+            if !new_dates.is_empty() {
+                changes.push(Change::NewWasteType {
+                    waste_type,
+                    dates: new_dates.into_iter().collect(),
+                });
+            }
+            continue;
+        };
+        let (Some(new_first), Some(new_last)) = (new_dates.first(), new_dates.last()) else {
+            continue;
+        };
+        let period = *old_first.max(new_first)..=*old_last.min(new_last);
+        let mut removed: Vec<NaiveDate> = old_dates
+            .difference(&new_dates)
+            .filter(|date| period.contains(date))
+            .copied()
+            .collect();
+        let mut added: Vec<NaiveDate> = new_dates
+            .difference(&old_dates)
+            .filter(|date| period.contains(date))
+            .copied()
+            .collect();
+        removed.retain(|from| {
+            let Some((index, to)) = added
+                .iter()
+                .enumerate()
+                .filter(|(_, to)| (**to - *from).num_days().abs() <= MAX_MOVE_DAYS)
+                .min_by_key(|(_, to)| (**to - *from).num_days().abs())
+                .map(|(index, to)| (index, *to))
+            else {
+                return true;
+            };
+            added.remove(index);
+            changes.push(Change::Moved {
+                waste_type,
+                from: *from,
+                to,
+            });
+            false
+        });
+        changes.extend(
+            removed
+                .into_iter()
+                .map(|date| Change::Removed { waste_type, date }),
+        );
+        changes.extend(
+            added
+                .into_iter()
+                .map(|date| Change::Added { waste_type, date }),
+        );
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        diff::{diff, Change},
+        garbage_client::{tests::date, WasteData, WasteType},
+        locale::Locale,
+    };
+
+    #[test]
+    fn test_diff() {
+        let old = WasteData {
+            residual_waste: vec![date(6, 16), date(6, 30), date(7, 14)],
+            organic_waste: vec![date(6, 7), date(6, 14), date(6, 21)],
+            paper_waste: vec![date(6, 14), date(7, 12), date(8, 9)],
+            ..WasteData::default()
+        };
+        let new = WasteData {
+            residual_waste: vec![date(6, 29), date(7, 14), date(7, 28)],
+            organic_waste: vec![date(6, 14), date(6, 21), date(6, 28)],
+            paper_waste: vec![date(6, 14), date(8, 9)],
+            bulky_waste: vec![date(7, 12)],
+            ..WasteData::default()
+        };
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                Change::Moved {
+                    waste_type: WasteType::Residual,
+                    from: date(6, 30),
+                    to: date(6, 29),
+                },
+                Change::Removed {
+                    waste_type: WasteType::Paper,
+                    date: date(7, 12),
+                },
+                Change::NewWasteType {
+                    waste_type: WasteType::Bulky,
+                    dates: vec![date(7, 12)],
+                },
+            ]
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_describe() {
+        let change = Change::Moved {
+            waste_type: WasteType::Residual,
+            from: date(6, 30),
+            to: date(6, 29),
+        };
+        assert_eq!(
+            change.describe(Locale::German),
+            "Restmüll: Termin vom 30.06.2023 auf den 29.06.2023 verschoben"
+        );
+        assert_eq!(
+            change.describe(Locale::English),
+            "Residual waste: collection moved from 2023-06-30 to 2023-06-29"
+        );
+    }
+}
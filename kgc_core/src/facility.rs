@@ -0,0 +1,491 @@
+//! This module provides the bundled dataset of drop-off facilities in Karlsruhe, e.g. recycling centres and container sites.
+//!
+//! The official website only links to these facilities, so the dataset is curated by hand.
+//! Its version is the month of the last review, it has to be updated when the city changes its facilities.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use ical::{
+    generator::{IcalCalendar, IcalCalendarBuilder, IcalEventBuilder, Property},
+    ical_property,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    address::normalize_street,
+    garbage_client::{escape_text, prod_id, timezone},
+    holiday::holiday,
+};
+
+static DATASET: &str = include_str!("facility/karlsruhe.json");
+static DOMAIN: &str = "karlsruhe.de";
+static LABEL_OPENING_HOURS: &str = "Öffnungszeiten";
+static FORMAT: &str = "%Y%m%d";
+
+/// A dataset of drop-off facilities.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dataset {
+    /// The month of the last review, e.g. "2023-06".
+    pub version: String,
+    /// Additional closing days of all facilities every year which are not public holidays, e.g. Heiligabend.
+    #[serde(default)]
+    pub closures: Vec<Closure>,
+    /// The neighbouring districts of every district, which rank the facilities of other districts.
+    #[serde(default)]
+    pub neighbours: BTreeMap<String, Vec<String>>,
+    pub facilities: Vec<Facility>,
+}
+
+/// A closing day of all facilities which recurs every year, e.g. on December 24th.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Closure {
+    pub month: u32,
+    pub day: u32,
+    pub name: String,
+}
+
+impl Closure {
+    /// Check whether this closing day falls on a date.
+    pub fn is_on(&self, date: NaiveDate) -> bool {
+        date.month() == self.month && date.day() == self.day
+    }
+}
+
+/// A drop-off facility.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Facility {
+    pub id: String,
+    pub name: String,
+    pub kind: FacilityKind,
+    pub street: String,
+    pub street_number: String,
+    pub district: String,
+    pub materials: Vec<Material>,
+    pub opening_hours: Vec<OpeningHours>,
+    /// Whether the facility is also accessible on public holidays and closing days, e.g. textile containers.
+    #[serde(default)]
+    pub open_on_holidays: bool,
+}
+
+/// The kind of a drop-off facility.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FacilityKind {
+    RecyclingCentre,
+    GlassContainer,
+    TextileContainer,
+    GreenWasteContainer,
+    BatteryContainer,
+}
+
+/// A material which is accepted by a drop-off facility.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Material {
+    Paper,
+    Glass,
+    Metal,
+    Wood,
+    Electronics,
+    GreenWaste,
+    BulkyWaste,
+    Batteries,
+    Textiles,
+}
+
+/// The opening hours of a drop-off facility on specific weekdays.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpeningHours {
+    pub weekdays: Vec<Weekday>,
+    pub open: NaiveTime,
+    pub close: NaiveTime,
+}
+
+/// A day on which a facility is closed although it is open on that weekday.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Exception<'a> {
+    pub facility: &'a Facility,
+    pub date: NaiveDate,
+    pub name: String,
+}
+
+impl Dataset {
+    /// Get the dataset which is bundled with this crate.
+    pub fn bundled() -> Self {
+        serde_json::from_str(DATASET).expect("the bundled dataset is valid")
+    }
+
+    /// Get the reason why a facility is closed on a date, although it may be open on that weekday.
+    pub fn closure(&self, facility: &Facility, date: NaiveDate) -> Option<String> {
+        if facility.open_on_holidays {
+            return None;
+        }
+        holiday(date)
+            .map(|holiday| String::from(holiday.name))
+            .or_else(|| {
+                self.closures
+                    .iter()
+                    .find(|closure| closure.is_on(date))
+                    .map(|closure| closure.name.clone())
+            })
+    }
+
+    /// Check whether a facility is open at a specific time.
+    pub fn is_open(&self, facility: &Facility, at: NaiveDateTime) -> bool {
+        self.closure(facility, at.date()).is_none() && facility.is_regularly_open(at)
+    }
+
+    /// Get the facilities which are open at a specific time.
+    pub fn open_at(&self, at: NaiveDateTime) -> Vec<&Facility> {
+        self.facilities
+            .iter()
+            .filter(|facility| self.is_open(facility, at))
+            .collect()
+    }
+
+    /// Get all facilities ordered by their distance to a street and a district.
+    ///
+    /// There are no coordinates, so the distance is estimated by the street and the district:
+    /// facilities at the same street come first, followed by the ones in the same district and in the neighbouring districts.
+    /// The street is not mapped to its district, so only the given district is compared,
+    /// i.e. without a district only the facilities at the same street are ranked before all others.
+    pub fn nearest(&self, street: Option<&str>, district: Option<&str>) -> Vec<&Facility> {
+        let street = street.map(normalize_street);
+        let district = district
+            .map(str::trim)
+            .filter(|district| !district.is_empty());
+        let neighbours = district
+            .and_then(|district| {
+                self.neighbours
+                    .iter()
+                    .find(|(name, _)| name.to_lowercase() == district.to_lowercase())
+            })
+            .map(|(_, neighbours)| neighbours.as_slice())
+            .unwrap_or_default();
+        let rank = |facility: &Facility| {
+            if street.as_ref() == Some(&normalize_street(&facility.street)) {
+                0
+            } else if district
+                .is_some_and(|district| district.to_lowercase() == facility.district.to_lowercase())
+            {
+                1
+            } else if neighbours.contains(&facility.district) {
+                2
+            } else {
+                3
+            }
+        };
+        let mut facilities: Vec<&Facility> = self.facilities.iter().collect();
+        facilities.sort_by_key(|facility| rank(facility));
+        facilities
+    }
+
+    /// Get the days between two dates on which facilities are closed although they are open on that weekday.
+    pub fn exceptions<'a>(
+        &self,
+        facilities: &[&'a Facility],
+        from: NaiveDate,
+        until: NaiveDate,
+    ) -> Vec<Exception<'a>> {
+        let mut exceptions = vec![];
+        let mut date = from;
+        while date <= until {
+            for facility in facilities {
+                let opens_on_weekday = facility
+                    .opening_hours
+                    .iter()
+                    .any(|opening_hours| opening_hours.weekdays.contains(&date.weekday()));
+                if let (true, Some(name)) = (opens_on_weekday, self.closure(facility, date)) {
+                    exceptions.push(Exception {
+                        facility,
+                        date,
+                        name,
+                    });
+                }
+            }
+            date += Duration::days(1);
+        }
+        exceptions
+    }
+}
+
+impl Facility {
+    /// Check whether this facility accepts a material.
+    pub fn accepts(&self, material: Material) -> bool {
+        self.materials.contains(&material)
+    }
+
+    /// Check whether this facility is open at a specific time by its opening hours, ignoring holidays.
+    fn is_regularly_open(&self, at: NaiveDateTime) -> bool {
+        self.opening_hours.iter().any(|opening_hours| {
+            opening_hours.weekdays.contains(&at.weekday())
+                && opening_hours.open <= at.time()
+                && at.time() < opening_hours.close
+        })
+    }
+
+    /// Describe the opening hours of this facility, e.g. "Mo–Fr 08:30–17:00, Sa 08:30–13:00".
+    pub fn describe_opening_hours(&self) -> String {
+        self.opening_hours
+            .iter()
+            .map(|opening_hours| {
+                let weekdays = match opening_hours.weekdays[..] {
+                    [] => String::new(),
+                    [weekday] => String::from(short_weekday(weekday)),
+                    [first, .., last]
+                        if opening_hours.weekdays.len() as u32
+                            == last.num_days_from_monday() - first.num_days_from_monday() + 1 =>
+                    {
+                        format!("{}–{}", short_weekday(first), short_weekday(last))
+                    }
+                    _ => opening_hours
+                        .weekdays
+                        .iter()
+                        .map(|weekday| short_weekday(*weekday))
+                        .collect::<Vec<&str>>()
+                        .join(", "),
+                };
+                format!(
+                    "{weekdays} {}–{}",
+                    opening_hours.open.format("%H:%M"),
+                    opening_hours.close.format("%H:%M")
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+impl fmt::Display for Facility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let address = format!("{} {}", self.street, self.street_number);
+        write!(
+            f,
+            "{} ({}, {}): {}",
+            self.name,
+            address.trim(),
+            self.district,
+            self.describe_opening_hours()
+        )
+    }
+}
+
+impl FromStr for Material {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(value.to_lowercase()))
+            .map_err(|_| format!("the material \"{value}\" is not supported"))
+    }
+}
+
+impl fmt::Display for Material {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+/// Build the calendar of the days on which facilities are closed although they are open on that weekday.
+pub fn get_exceptions_calendar(exceptions: &[Exception]) -> IcalCalendar {
+    let changed = chrono::Local::now().format("%Y%m%dT%H%M%S").to_string();
+    let mut calendar = IcalCalendarBuilder::version("2.0")
+        .gregorian()
+        .prodid(prod_id(DOMAIN, Some(String::from(LABEL_OPENING_HOURS))))
+        .add_tz(timezone())
+        .build();
+    for exception in exceptions {
+        let facility = exception.facility;
+        let event = IcalEventBuilder::tzid("Europe/Berlin")
+            .uid(format!(
+                "{LABEL_OPENING_HOURS}_{}_{}@{DOMAIN}",
+                facility.id,
+                exception.date.format(FORMAT)
+            ))
+            .changed(changed.clone())
+            .one_day(exception.date.format(FORMAT).to_string())
+            .set(ical_property!(
+                "SUMMARY",
+                escape_text(&format!("{} geschlossen", facility.name))
+            ))
+            .set(ical_property!(
+                "LOCATION",
+                escape_text(
+                    format!(
+                        "{} {}, {}",
+                        facility.street, facility.street_number, facility.district
+                    )
+                    .replace(" ,", ",")
+                    .as_str()
+                )
+            ))
+            .set(ical_property!(
+                "DESCRIPTION",
+                escape_text(&format!(
+                    "{}\nReguläre Öffnungszeiten: {}",
+                    exception.name,
+                    facility.describe_opening_hours()
+                ))
+            ))
+            .set(ical_property!("TRANSP", "TRANSPARENT"))
+            .build();
+        calendar.events.push(event);
+    }
+    calendar
+}
+
+/// Get the German abbreviation of a weekday.
+fn short_weekday(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Mo",
+        Weekday::Tue => "Di",
+        Weekday::Wed => "Mi",
+        Weekday::Thu => "Do",
+        Weekday::Fri => "Fr",
+        Weekday::Sat => "Sa",
+        Weekday::Sun => "So",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use ical::generator::Emitter;
+
+    use crate::{
+        facility::{get_exceptions_calendar, Dataset, Facility, Material},
+        garbage_client::tests::date,
+    };
+
+    fn date_time(month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        date(month, day).and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    /// Test whether the bundled dataset is valid and consistent.
+    #[test]
+    fn test_bundled() {
+        let dataset = Dataset::bundled();
+        assert!(!dataset.facilities.is_empty());
+        for facility in &dataset.facilities {
+            assert!(!facility.materials.is_empty(), "{}", facility.id);
+            assert!(!facility.opening_hours.is_empty(), "{}", facility.id);
+            assert!(
+                dataset.neighbours.contains_key(&facility.district),
+                "{}",
+                facility.district
+            );
+        }
+        for neighbours in dataset.neighbours.values() {
+            for neighbour in neighbours {
+                assert!(dataset.neighbours.contains_key(neighbour), "{neighbour}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_at() {
+        let dataset = Dataset::bundled();
+        let ids = |at| -> Vec<String> {
+            dataset
+                .open_at(at)
+                .into_iter()
+                .filter(|facility| facility.accepts(Material::GreenWaste))
+                .map(|facility| facility.id.clone())
+                .collect()
+        };
+        // Wednesday afternoon
+        assert_eq!(
+            ids(date_time(6, 14, 15, 0)),
+            vec![
+                "wertstoffstation-ost",
+                "wertstoffstation-neureut",
+                "wertstoffstation-west",
+                "gruenabfall-rintheim",
+                "gruenabfall-daxlanden"
+            ]
+        );
+        // Fronleichnam
+        assert!(ids(date_time(6, 8, 15, 0)).is_empty());
+        // Textile containers are accessible on holidays.
+        assert_eq!(dataset.open_at(date_time(6, 8, 15, 0)).len(), 2);
+    }
+
+    #[test]
+    fn test_nearest() {
+        let dataset = Dataset::bundled();
+        let nearest: Vec<&str> = dataset
+            .nearest(Some("Pfinztalstr."), Some("durlach"))
+            .into_iter()
+            .filter(|facility| facility.accepts(Material::Textiles))
+            .map(|facility| facility.id.as_str())
+            .collect();
+        assert_eq!(
+            nearest,
+            vec![
+                "alttextil-pfinztalstrasse",
+                "wertstoffstation-ost",
+                "wertstoffstation-west",
+                "alttextil-gutenbergplatz"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exceptions() {
+        let dataset = Dataset::bundled();
+        let facility = dataset
+            .facilities
+            .iter()
+            .find(|facility| facility.id == "wertstoffstation-durlach")
+            .unwrap();
+        assert_eq!(
+            facility.describe_opening_hours(),
+            "Di, Do, Fr 12:00–18:00, Sa 08:30–13:00"
+        );
+        let exceptions = dataset.exceptions(&[facility], date(5, 1), date(6, 30));
+        let dates: Vec<NaiveDate> = exceptions.iter().map(|exception| exception.date).collect();
+        // Tag der Arbeit and Pfingstmontag are Mondays, on which the facility is closed anyway.
+        assert_eq!(dates, vec![date(5, 18), date(6, 8)]);
+        assert_eq!(exceptions[0].name, "Christi Himmelfahrt");
+
+        let calendar = get_exceptions_calendar(&exceptions);
+        assert_eq!(calendar.events.len(), 2);
+    }
+
+    /// Test whether the long lines of the calendar of all facilities are folded without splitting an umlaut.
+    #[test]
+    fn test_get_exceptions_calendar_folding() {
+        let dataset = Dataset::bundled();
+        let facilities: Vec<&Facility> = dataset.facilities.iter().collect();
+        let exceptions = dataset.exceptions(&facilities, date(1, 1), date(12, 31));
+        let ics = get_exceptions_calendar(&exceptions).generate();
+        assert!(ics.lines().all(|line| line.chars().count() <= 75));
+    }
+
+    /// Test whether the closing days recur every year.
+    #[test]
+    fn test_closure() {
+        let dataset = Dataset::bundled();
+        let facility = dataset
+            .facilities
+            .iter()
+            .find(|facility| facility.id == "wertstoffstation-ost")
+            .unwrap();
+        for year in [2023, 2024, 2030] {
+            let heiligabend = NaiveDate::from_ymd_opt(year, 12, 24).unwrap();
+            let silvester = NaiveDate::from_ymd_opt(year, 12, 31).unwrap();
+            assert_eq!(
+                dataset.closure(facility, heiligabend).as_deref(),
+                Some("Heiligabend")
+            );
+            assert_eq!(
+                dataset.closure(facility, silvester).as_deref(),
+                Some("Silvester")
+            );
+            assert!(dataset
+                .closure(facility, heiligabend.pred_opt().unwrap())
+                .is_none());
+        }
+    }
+}
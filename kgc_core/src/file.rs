@@ -0,0 +1,71 @@
+//! This module writes files atomically, so that a half-written file is never read.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+/// Write a file atomically, e.g. so that a synchronization client or a concurrent request never reads a half-written file.
+///
+/// The content is written to a temporary file with a unique name in the same directory, which then replaces the file.
+/// The temporary file is removed if the file cannot be written.
+pub fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("the path {} is not a file", path.display()))?;
+    let directory = match path.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&directory)?;
+    let prefix = format!(".{}.", file_name.to_string_lossy());
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(&prefix).suffix(".tmp");
+    // The temporary file is only readable by the owner by default, but the file is created like any other file.
+    #[cfg(unix)]
+    builder.permissions(std::os::unix::fs::PermissionsExt::from_mode(0o666));
+    let mut file = builder.tempfile_in(&directory)?;
+    file.write_all(content)?;
+    file.as_file().sync_all()?;
+    file.persist(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs};
+
+    use crate::file::write_atomically;
+
+    /// Test whether a file is replaced and no temporary file is left behind, even if the file cannot be written.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_write_atomically() {
+        let directory = temp_dir().join("kgc_test_write_atomically");
+        let _ = fs::remove_dir_all(&directory);
+        let path = directory.join("calendar.ics");
+        write_atomically(&path, b"first").unwrap();
+        write_atomically(&path, b"second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_ne!(mode & 0o044, 0, "the file is only readable by its owner");
+        }
+        let occupied = directory.join("occupied");
+        fs::create_dir_all(occupied.join("calendar")).unwrap();
+        assert!(write_atomically(&occupied, b"third").is_err());
+        let mut names: Vec<String> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["calendar.ics", "occupied"]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,28 +1,46 @@
-//! This client fetches garbage and parses it into waste data.
+//! This client builds calendars from the waste data of a provider and holds the waste data model.
 
-use std::collections::HashMap;
+use std::{collections::BTreeMap, error::Error, fmt, str::FromStr};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use bitmask_enum::bitmask;
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate, NaiveTime};
 use ical::{
     generator::{IcalCalendar, IcalCalendarBuilder, IcalEvent, IcalEventBuilder, Property},
     ical_param, ical_property,
+    parser::ical::component::{
+        IcalAlarm, IcalTimeZone, IcalTimeZoneTransition, IcalTimeZoneTransitionType,
+    },
+    IcalParser,
 };
-use regex::{Captures, Regex};
-use reqwest::Response;
-use scraper::{Html, Selector};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
-static URL: &str = "https://web6.karlsruhe.de/service/abfall/akal/akal.php";
-static PROD_ID: [&str; 2] = ["Abfuhrkalender", "karlsruhe.de"];
+use crate::{
+    address::Address,
+    holiday::holiday,
+    locale::Locale,
+    prediction::Rhythm,
+    provider::Provider,
+    render::{Assignee, Blackout, RenderOptions},
+};
+
+static PROD_ID: &str = "Abfuhrkalender";
 static TIMEZONE: &str = "Europe/Berlin";
 static FORMAT: &str = "%Y%m%d";
+static DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+/// The duration of the events of the collections at a time of day.
+static TIME_OF_DAY_DURATION: &str = "PT1H";
 
 static LABEL_RESIDUAL: &str = "Restmüll";
 static LABEL_ORGANIC: &str = "Bioabfall";
 static LABEL_RECYCLABLE: &str = "Wertstoff";
 static LABEL_PAPER: &str = "Papier";
 static LABEL_BULKY: &str = "Sperrmüllabholung";
+static LABEL_HAZARDOUS: &str = "Schadstoffsammlung";
+static LABEL_PREDICTION: &str = "Prognose";
+static LABEL_SHIFTED: &str = "verschoben";
+static LABEL_BLACKOUT: &str = "abwesend";
 
 #[bitmask]
 #[bitmask_config(inverted_flags)]
@@ -32,214 +50,610 @@ pub enum WasteTypeBitmask {
     Recyclable,
     Paper,
     Bulky,
+    Hazardous,
+}
+
+/// A waste type which is collected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WasteType {
+    Residual,
+    Organic,
+    Recyclable,
+    Paper,
+    Bulky,
+    /// The mobile hazardous waste collection, which stops at specific locations for a few hours.
+    Hazardous,
+}
+
+impl WasteType {
+    pub const ALL: [WasteType; 6] = [
+        WasteType::Residual,
+        WasteType::Organic,
+        WasteType::Recyclable,
+        WasteType::Paper,
+        WasteType::Bulky,
+        WasteType::Hazardous,
+    ];
+
+    /// Get the label which is used by the official website.
+    pub fn label(&self) -> &'static str {
+        match self {
+            WasteType::Residual => LABEL_RESIDUAL,
+            WasteType::Organic => LABEL_ORGANIC,
+            WasteType::Recyclable => LABEL_RECYCLABLE,
+            WasteType::Paper => LABEL_PAPER,
+            WasteType::Bulky => LABEL_BULKY,
+            WasteType::Hazardous => LABEL_HAZARDOUS,
+        }
+    }
+
+    pub fn bitmask(&self) -> WasteTypeBitmask {
+        match self {
+            WasteType::Residual => WasteTypeBitmask::Residual,
+            WasteType::Organic => WasteTypeBitmask::Organic,
+            WasteType::Recyclable => WasteTypeBitmask::Recyclable,
+            WasteType::Paper => WasteTypeBitmask::Paper,
+            WasteType::Bulky => WasteTypeBitmask::Bulky,
+            WasteType::Hazardous => WasteTypeBitmask::Hazardous,
+        }
+    }
+}
+
+impl FromStr for WasteType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "residual" => Ok(WasteType::Residual),
+            "organic" => Ok(WasteType::Organic),
+            "recyclable" => Ok(WasteType::Recyclable),
+            "paper" => Ok(WasteType::Paper),
+            "bulky" => Ok(WasteType::Bulky),
+            "hazardous" => Ok(WasteType::Hazardous),
+            _ => Err(format!("the waste type \"{value}\" is not supported")),
+        }
+    }
+}
+
+impl fmt::Display for WasteType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WasteType::Residual => write!(f, "residual"),
+            WasteType::Organic => write!(f, "organic"),
+            WasteType::Recyclable => write!(f, "recyclable"),
+            WasteType::Paper => write!(f, "paper"),
+            WasteType::Bulky => write!(f, "bulky"),
+            WasteType::Hazardous => write!(f, "hazardous"),
+        }
+    }
 }
 
-/// Get the calendar for a specific street and street number.
+/// Get the calendar for a specific address of a provider.
+///
+/// The address is resolved against the official street list before the official server is asked for the dates.
 pub async fn get(
-    street: &str,
-    street_number: &str,
+    provider: &dyn Provider,
+    address: &Address,
     excluded_waste_types: WasteTypeBitmask,
 ) -> Result<IcalCalendar> {
-    let response = get_response(street, street_number).await?;
-    let waste_data = parse(&response.text().await?)?;
-    let calendar = get_calendar(street, street_number, waste_data, excluded_waste_types);
+    let (address, waste_data) = provider.fetch(address).await?;
+    let calendar = get_calendar(
+        provider,
+        &address.requested.0,
+        &address.requested.1,
+        waste_data,
+        excluded_waste_types,
+        &RenderOptions::default(),
+    );
     Ok(calendar)
 }
 
-/// Get the HTML response from the official server.
-async fn get_response(street: &str, street_number: &str) -> Result<Response> {
-    let client = reqwest::Client::new();
-    let response = client
-        .post(URL)
-        .form(&HashMap::from([
-            ("strasse_n", street),
-            ("hausnr", street_number),
-        ]))
-        .send()
-        .await?;
-    Ok(response)
-}
-
 /// Build the calendar from the waste data.
-fn get_calendar(
+///
+/// The render options filter the dates by a date window and blackouts,
+/// and decide whether shifted collections and dates in a blackout get their own events.
+/// The street and the street number are the requested ones (see [`Address::requested`]), because the UIDs are built from them.
+pub fn get_calendar(
+    provider: &dyn Provider,
     street: &str,
     street_number: &str,
     waste_data: WasteData,
     excluded_waste_types: WasteTypeBitmask,
+    options: &RenderOptions,
 ) -> IcalCalendar {
     let changed = chrono::Local::now().format("%Y%m%dT%H%M%S").to_string();
+    let today = chrono::Local::now().date_naive();
     let prod_id_label = match excluded_waste_types {
         WasteTypeBitmask::InvertedResidual => Some(String::from(LABEL_RESIDUAL)),
         WasteTypeBitmask::InvertedOrganic => Some(String::from(LABEL_ORGANIC)),
         WasteTypeBitmask::InvertedRecyclable => Some(String::from(LABEL_RECYCLABLE)),
         WasteTypeBitmask::InvertedPaper => Some(String::from(LABEL_PAPER)),
         WasteTypeBitmask::InvertedBulky => Some(String::from(LABEL_BULKY)),
+        WasteTypeBitmask::InvertedHazardous => Some(String::from(LABEL_HAZARDOUS)),
         _ => None,
     };
     let mut calendar = IcalCalendarBuilder::version("2.0")
         .gregorian()
-        .prodid(prod_id(prod_id_label))
+        .prodid(prod_id(provider.domain(), prod_id_label))
+        .add_tz(timezone())
         .build();
-    for (label, dates, waste_type_bitmask) in [
-        (
-            LABEL_RESIDUAL,
-            waste_data.residual_waste,
-            WasteTypeBitmask::Residual,
-        ),
-        (
-            LABEL_ORGANIC,
-            waste_data.organic_waste,
-            WasteTypeBitmask::Organic,
-        ),
-        (
-            LABEL_RECYCLABLE,
-            waste_data.recyclable_waste,
-            WasteTypeBitmask::Recyclable,
-        ),
-        (LABEL_PAPER, waste_data.paper_waste, WasteTypeBitmask::Paper),
-        (
-            LABEL_BULKY,
-            waste_data.bulky_waste.into_iter().collect(),
-            WasteTypeBitmask::Bulky,
-        ),
-    ] {
-        if let (Some(event), false) = (
-            get_event(street, street_number, dates, label, &changed),
-            excluded_waste_types.contains(waste_type_bitmask),
+    let location = Location {
+        provider,
+        street,
+        street_number,
+    };
+    let regular_dates: BTreeMap<WasteType, Vec<NaiveDate>> = WasteType::ALL
+        .into_iter()
+        .map(|waste_type| {
+            let regular_dates = waste_data
+                .collections(waste_type)
+                .into_iter()
+                .map(|collection| collection.shifted_from.unwrap_or(collection.date))
+                .chain(
+                    waste_data
+                        .predictions
+                        .get(&waste_type)
+                        .into_iter()
+                        .flatten()
+                        .copied(),
+                )
+                .collect();
+            (waste_type, regular_dates)
+        })
+        .collect();
+    let assignee_of = |waste_type: WasteType, date: NaiveDate, shifted_from: Option<NaiveDate>| {
+        options.rotation.as_ref().and_then(|rotation| {
+            rotation.assignee(shifted_from.unwrap_or(date), &regular_dates[&waste_type])
+        })
+    };
+    for waste_type in WasteType::ALL {
+        if excluded_waste_types.contains(waste_type.bitmask()) {
+            continue;
+        }
+        let (marked_collections, collections): (Vec<Collection>, Vec<Collection>) = waste_data
+            .collections(waste_type)
+            .into_iter()
+            .filter(|collection| options.is_rendered(collection.date, today))
+            .partition(|collection| options.is_marked(collection.date));
+        let (stop_collections, collections): (Vec<Collection>, Vec<Collection>) =
+            collections.into_iter().partition(|collection| {
+                waste_data
+                    .stops
+                    .iter()
+                    .any(|stop| waste_type == WasteType::Hazardous && stop.date == collection.date)
+            });
+        let (shifted_collections, collections): (Vec<Collection>, Vec<Collection>) = collections
+            .into_iter()
+            .partition(|collection| options.mark_shifted && collection.shifted_from.is_some());
+        for (assignee, dates) in group_by_assignee(collections.iter().map(|collection| {
+            (
+                collection.date,
+                assignee_of(waste_type, collection.date, collection.shifted_from),
+            )
+        })) {
+            calendar.events.extend(get_event(
+                &location,
+                options,
+                dates,
+                provider.label(waste_type),
+                &changed,
+                EventKind::Published,
+                assignee,
+            ));
+        }
+        for collection in &shifted_collections {
+            calendar.events.extend(get_event(
+                &location,
+                options,
+                vec![collection.date],
+                provider.label(waste_type),
+                &changed,
+                EventKind::Shifted(collection),
+                assignee_of(waste_type, collection.date, collection.shifted_from),
+            ));
+        }
+        for stop in waste_data.stops.iter().filter(|stop| {
+            stop_collections
+                .iter()
+                .any(|collection| collection.date == stop.date)
+        }) {
+            calendar.events.extend(get_event(
+                &location,
+                options,
+                vec![stop.date],
+                provider.label(waste_type),
+                &changed,
+                EventKind::Stop(stop),
+                assignee_of(waste_type, stop.date, None),
+            ));
+        }
+        let marked_dates: Vec<NaiveDate> = marked_collections
+            .iter()
+            .map(|collection| collection.date)
+            .collect();
+        for (blackout, dates) in options.marked(&marked_dates) {
+            for (assignee, dates) in group_by_assignee(dates.into_iter().map(|date| {
+                let shifted_from = marked_collections
+                    .iter()
+                    .find(|collection| collection.date == date)
+                    .and_then(|collection| collection.shifted_from);
+                (date, assignee_of(waste_type, date, shifted_from))
+            })) {
+                calendar.events.extend(get_event(
+                    &location,
+                    options,
+                    dates,
+                    provider.label(waste_type),
+                    &changed,
+                    EventKind::Blackout(blackout, false),
+                    assignee,
+                ));
+            }
+        }
+    }
+    for (waste_type, predictions) in &waste_data.predictions {
+        if excluded_waste_types.contains(waste_type.bitmask()) {
+            continue;
+        }
+        let (marked_predictions, predictions): (Vec<NaiveDate>, Vec<NaiveDate>) = predictions
+            .iter()
+            .copied()
+            .filter(|date| options.is_rendered(*date, today))
+            .partition(|date| options.is_marked(*date));
+        for (assignee, dates) in group_by_assignee(
+            predictions
+                .into_iter()
+                .map(|date| (date, assignee_of(*waste_type, date, None))),
         ) {
-            calendar.events.push(event);
+            calendar.events.extend(get_event(
+                &location,
+                options,
+                dates,
+                provider.label(*waste_type),
+                &changed,
+                EventKind::Predicted,
+                assignee,
+            ));
+        }
+        for (blackout, dates) in options.marked(&marked_predictions) {
+            for (assignee, dates) in group_by_assignee(
+                dates
+                    .into_iter()
+                    .map(|date| (date, assignee_of(*waste_type, date, None))),
+            ) {
+                calendar.events.extend(get_event(
+                    &location,
+                    options,
+                    dates,
+                    provider.label(*waste_type),
+                    &changed,
+                    EventKind::Blackout(blackout, true),
+                    assignee,
+                ));
+            }
         }
     }
+    for notice in waste_data.notices {
+        calendar.properties.push(ical_property!(
+            "X-KGC-NOTICE",
+            escape_text(&notice.to_string())
+        ));
+    }
     calendar
 }
 
-/// Build an event from a vector of dates.
-fn get_event(
+/// Parse a calendar which has been built from waste data back to waste data.
+///
+/// The waste type of an event is determined by its UID with the labels of the provider which built the calendar,
+/// tentative events of predicted dates are skipped.
+pub fn parse_calendar(ics: &str, provider: &dyn Provider) -> Result<WasteData> {
+    let mut waste_data = WasteData::default();
+    for calendar in IcalParser::new(ics.as_bytes()) {
+        for event in calendar?.events {
+            let values = |name: &'static str| {
+                event
+                    .properties
+                    .iter()
+                    .filter(move |property| property.name == name)
+                    .filter_map(|property| property.value.as_deref())
+            };
+            if values("STATUS").any(|status| status == "TENTATIVE") {
+                continue;
+            }
+            let Some(waste_type) = values("UID").find_map(|uid| waste_type_of_uid(uid, provider))
+            else {
+                continue;
+            };
+            let dates = waste_data.dates_mut(waste_type);
+            dates.extend(
+                values("DTSTART")
+                    .chain(values("RDATE"))
+                    .flat_map(|value| value.split(','))
+                    .filter_map(|value| NaiveDate::parse_from_str(value.get(..8)?, FORMAT).ok()),
+            );
+            dates.sort();
+            dates.dedup();
+        }
+    }
+    Ok(waste_data)
+}
+
+/// Check whether two generated calendars are equal apart from their time stamps, which change with every generation.
+pub fn is_same_calendar(old: &str, new: &str) -> bool {
+    let content = |ics: &'_ str| {
+        ics.lines()
+            .filter(|line| !line.starts_with("DTSTAMP"))
+            .map(str::to_owned)
+            .collect::<Vec<String>>()
+    };
+    content(old) == content(new)
+}
+
+/// Merge a built calendar into an existing calendar, e.g. a personal calendar file.
+///
+/// The events of the existing calendar whose UIDs belong to the address are replaced by the events of the built calendar,
+/// so dates which are no longer valid are removed. All other components and properties are kept,
+/// and the time zones of the built calendar are added unless a time zone with the same `TZID` exists.
+pub fn merge_calendar(
+    existing: &str,
+    calendar: IcalCalendar,
+    provider: &dyn Provider,
     street: &str,
     street_number: &str,
+) -> Result<IcalCalendar> {
+    let mut calendars = IcalParser::new(existing.as_bytes());
+    let Some(mut merged) = calendars.next().transpose()? else {
+        return Ok(calendar);
+    };
+    if calendars.next().is_some() {
+        bail!("the existing file contains more than one calendar");
+    }
+    merged.events.retain(|event| {
+        !event.properties.iter().any(|property| {
+            property.name == "UID"
+                && property
+                    .value
+                    .as_deref()
+                    .is_some_and(|value| is_uid_of_address(value, provider, street, street_number))
+        })
+    });
+    merged.events.extend(calendar.events);
+    let tzid = |timezone: &IcalTimeZone| {
+        timezone
+            .properties
+            .iter()
+            .find(|property| property.name == "TZID")
+            .and_then(|property| property.value.clone())
+    };
+    for timezone in calendar.timezones {
+        if !merged
+            .timezones
+            .iter()
+            .any(|existing| tzid(existing) == tzid(&timezone))
+        {
+            merged.timezones.push(timezone);
+        }
+    }
+    Ok(merged)
+}
+
+/// The kind of an event, which determines its UID and additional properties.
+///
+/// Predicted and shifted events get their own UIDs, so they do not collide with the published dates.
+/// Stops of the mobile hazardous waste collection are timed events at the location of the stop.
+/// Dates in a blackout are marked per blackout, predicted ones stay tentative.
+enum EventKind<'a> {
+    Published,
+    Predicted,
+    Shifted(&'a Collection),
+    Stop(&'a Stop),
+    Blackout(&'a Blackout, bool),
+}
+
+/// The location of the events of a calendar.
+struct Location<'a> {
+    provider: &'a dyn Provider,
+    street: &'a str,
+    street_number: &'a str,
+}
+
+/// Group dates by their assignee, keeping the order of the first date of each assignee.
+fn group_by_assignee<'a>(
+    dates: impl IntoIterator<Item = (NaiveDate, Option<&'a Assignee>)>,
+) -> Vec<(Option<&'a Assignee>, Vec<NaiveDate>)> {
+    let mut groups: Vec<(Option<&Assignee>, Vec<NaiveDate>)> = vec![];
+    for (date, assignee) in dates {
+        match groups.iter_mut().find(|(other, _)| *other == assignee) {
+            Some((_, dates)) => dates.push(date),
+            None => groups.push((assignee, vec![date])),
+        }
+    }
+    groups
+}
+
+/// Build an event from a vector of dates.
+///
+/// The assignee of a rotation is appended to the summary and added as attendee.
+/// The events of the collections are at the time of day of the options, and all events but the ones of blackouts get their alarms.
+fn get_event(
+    location: &Location,
+    options: &RenderOptions,
     dates: Vec<NaiveDate>,
     summary: &str,
     changed: &str,
+    kind: EventKind,
+    assignee: Option<&Assignee>,
 ) -> Option<IcalEvent> {
     if dates.is_empty() {
         return None;
     }
-    Some(
-        IcalEventBuilder::tzid(TIMEZONE)
-            .uid(uid(street, street_number, summary))
-            .changed(changed)
-            .one_day(dates.get(0).unwrap().format(FORMAT).to_string())
-            .set(ical_property!("SUMMARY", summary))
-            .set(ical_property!(
-                "RDATE",
-                dates
-                    .into_iter()
-                    .map(|date| date.format(FORMAT).to_string())
-                    .collect::<Vec<String>>()
-                    .join(","),
-                ical_param!("VALUE", "DATE")
-            ))
-            .set(ical_property!(
-                "LOCATION",
-                format!("{street} {street_number}, Karlsruhe")
-            ))
-            .set(ical_property!("DESCRIPTION", URL))
-            .set(ical_property!("TRANSP", "TRANSPARENT"))
-            .build(),
-    )
-}
-
-/// Parse the garbage HTML to usable waste data.
-fn parse(html: &str) -> Result<WasteData> {
-    let dom = Html::parse_document(html);
-    let row_selector = Selector::parse(".row").unwrap();
-    let rows = dom.select(&row_selector);
-    let mut residual_waste_dates: Vec<NaiveDate> = vec![];
-    let mut organic_waste_dates: Vec<NaiveDate> = vec![];
-    let mut recyclable_waste_dates: Vec<NaiveDate> = vec![];
-    let mut paper_waste_dates: Vec<NaiveDate> = vec![];
-    let mut bulky_waste_date: Option<NaiveDate> = None;
-    let type_col_selector = Selector::parse(".col_3-2").unwrap();
-    let date_col_selector = Selector::parse(".col_3-3").unwrap();
-    let bulky_waste_date_col_selector = Selector::parse(".col_4-3").unwrap();
-    let date_regex = Regex::new(
-        r"(?x)
-            >\s* # the ending of the previous tag
-            \w{2}\.\s # the day of the week in short notation with a dot and a space
-            den\s
-            (?P<day>\d{2}) # the day
-            \.
-            (?P<month>\d{2}) # the month
-            \.
-            (?P<year>\d{4}) # the year
-        ",
-    )
-    .unwrap();
-    let bulky_waste_date_regex =
-        Regex::new(r">\s*(?P<day>\d{2})\.(?P<month>\d{2})\.(?P<year>\d{4})").unwrap();
-    let date_from_captures = |captures: Captures| -> Option<NaiveDate> {
-        let day: u32 = captures["day"].parse().unwrap();
-        let month: u32 = captures["month"].parse().unwrap();
-        let year: i32 = captures["year"].parse().unwrap();
-        NaiveDate::from_ymd_opt(year, month, day)
-    };
-    let find_dates = |inner_html: &str| -> Vec<NaiveDate> {
-        date_regex
-            .captures_iter(inner_html)
-            .filter_map(date_from_captures)
-            .collect()
-    };
-    for row_element in rows {
-        let Some(type_col) = row_element.select(&type_col_selector).next() else {
-            continue;
+    let Location {
+        provider,
+        street,
+        street_number,
+    } = location;
+    let event_uid = |summary: &str| {
+        let summary = match assignee {
+            Some(assignee) => format!(
+                "{summary} {}",
+                assignee
+                    .name
+                    .chars()
+                    .filter(|char| char.is_alphanumeric())
+                    .collect::<String>()
+            ),
+            None => String::from(summary),
         };
-        let type_col_inner_html = type_col.inner_html();
-        let date_col_inner_html_option = row_element
-            .select(&date_col_selector)
-            .next()
-            .map(|date_col| date_col.inner_html());
-        let bulky_waste_date_col_inner_html_option = row_element
-            .select(&bulky_waste_date_col_selector)
-            .next()
-            .map(|date_col| date_col.inner_html());
-        match (
-            date_col_inner_html_option,
-            bulky_waste_date_col_inner_html_option,
-        ) {
-            (Some(date_col_inner_html), _) if type_col_inner_html.contains(LABEL_RESIDUAL) => {
-                residual_waste_dates = find_dates(&date_col_inner_html);
-            }
-            (Some(date_col_inner_html), _) if type_col_inner_html.contains(LABEL_ORGANIC) => {
-                organic_waste_dates = find_dates(&date_col_inner_html);
-            }
-            (Some(date_col_inner_html), _) if type_col_inner_html.contains(LABEL_RECYCLABLE) => {
-                recyclable_waste_dates = find_dates(&date_col_inner_html);
-            }
-            (Some(date_col_inner_html), _) if type_col_inner_html.contains(LABEL_PAPER) => {
-                paper_waste_dates = find_dates(&date_col_inner_html);
-            }
-            (_, Some(bulky_waste_date_col_inner_html))
-                if type_col_inner_html.contains(LABEL_BULKY) =>
-            {
-                bulky_waste_date = bulky_waste_date_regex
-                    .captures(&bulky_waste_date_col_inner_html)
-                    .and_then(date_from_captures);
-            }
-            _ => continue,
+        uid(provider.domain(), street, street_number, &summary)
+    };
+    let mut event_location = format!("{street} {street_number}, {}", provider.municipality());
+    let (uid, mut summary, description) = match kind {
+        EventKind::Published => (
+            event_uid(summary),
+            String::from(summary),
+            String::from(provider.url()),
+        ),
+        EventKind::Predicted => (
+            event_uid(&format!("{summary} {LABEL_PREDICTION}")),
+            String::from(summary),
+            String::from(provider.url()),
+        ),
+        EventKind::Shifted(collection) => (
+            event_uid(&format!(
+                "{summary} {LABEL_SHIFTED} {}",
+                collection.date.format(FORMAT)
+            )),
+            format!("{summary} ⚠ {LABEL_SHIFTED}"),
+            escape_text(&format!(
+                "{}\n{}",
+                collection
+                    .shift_description(Locale::German)
+                    .unwrap_or_default(),
+                provider.url()
+            )),
+        ),
+        EventKind::Blackout(blackout, predicted) => {
+            let prediction = if predicted {
+                format!(" {LABEL_PREDICTION}")
+            } else {
+                String::new()
+            };
+            (
+                event_uid(&format!(
+                    "{summary}{prediction} {LABEL_BLACKOUT} {}",
+                    blackout.from.format(FORMAT)
+                )),
+                format!("{summary} ✈ {LABEL_BLACKOUT}"),
+                escape_text(&format!("{}\n{}", blackout.describe(), provider.url())),
+            )
+        }
+        EventKind::Stop(stop) => {
+            event_location = escape_text(&format!("{}, {}", stop.location, stop.district));
+            (
+                event_uid(&format!(
+                    "{summary} {}{}",
+                    stop.date.format(FORMAT),
+                    stop.start.format("%H%M")
+                )),
+                String::from(summary),
+                escape_text(&format!("{}\n{}", stop.describe(), provider.url())),
+            )
         }
+    };
+    if let Some(assignee) = assignee {
+        summary = format!("{summary} – {}", assignee.name);
     }
-    let waste_data = WasteData {
-        residual_waste: residual_waste_dates,
-        organic_waste: organic_waste_dates,
-        recyclable_waste: recyclable_waste_dates,
-        paper_waste: paper_waste_dates,
-        bulky_waste: bulky_waste_date,
+    let builder = IcalEventBuilder::tzid(TIMEZONE).uid(uid).changed(changed);
+    let builder = match kind {
+        EventKind::Stop(stop) => builder
+            .start(
+                stop.date
+                    .and_time(stop.start)
+                    .format(DATE_TIME_FORMAT)
+                    .to_string(),
+            )
+            .end(
+                stop.date
+                    .and_time(stop.end)
+                    .format(DATE_TIME_FORMAT)
+                    .to_string(),
+            ),
+        _ => match options.time_of_day {
+            Some(time) => builder
+                .start(
+                    dates
+                        .first()
+                        .unwrap()
+                        .and_time(time)
+                        .format(DATE_TIME_FORMAT)
+                        .to_string(),
+                )
+                .duration(TIME_OF_DAY_DURATION)
+                .set(ical_property!(
+                    "RDATE",
+                    dates
+                        .into_iter()
+                        .map(|date| date.and_time(time).format(DATE_TIME_FORMAT).to_string())
+                        .collect::<Vec<String>>()
+                        .join(","),
+                    ical_param!("TZID", TIMEZONE)
+                )),
+            None => builder
+                .one_day(dates.first().unwrap().format(FORMAT).to_string())
+                .set(ical_property!(
+                    "RDATE",
+                    dates
+                        .into_iter()
+                        .map(|date| date.format(FORMAT).to_string())
+                        .collect::<Vec<String>>()
+                        .join(","),
+                    ical_param!("VALUE", "DATE")
+                )),
+        },
     };
-    Ok(waste_data)
+    let mut event = builder
+        .set(ical_property!("SUMMARY", summary.clone()))
+        .set(ical_property!("LOCATION", event_location))
+        .set(ical_property!("DESCRIPTION", description))
+        .set(ical_property!("TRANSP", "TRANSPARENT"))
+        .build();
+    if let Some(assignee) = assignee {
+        event.properties.push(ical_property!(
+            "ATTENDEE",
+            assignee.cal_address(),
+            ical_param!("CN", &assignee.name)
+        ));
+    }
+    if let EventKind::Predicted | EventKind::Blackout(_, true) = kind {
+        event.properties.push(ical_property!("STATUS", "TENTATIVE"));
+    }
+    if !matches!(kind, EventKind::Blackout(..)) {
+        event.alarms = options
+            .alarms
+            .iter()
+            .map(|alarm| IcalAlarm {
+                properties: vec![
+                    ical_property!("ACTION", "DISPLAY"),
+                    ical_property!("DESCRIPTION", summary.clone()),
+                    ical_property!("TRIGGER", alarm.trigger()),
+                ],
+            })
+            .collect();
+    }
+    Some(event)
+}
+
+/// Escape a text value as defined in RFC 5545.
+pub(crate) fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
 }
 
-fn prod_id(label: Option<String>) -> String {
-    let mut strings: Vec<String> = Vec::from(PROD_ID).into_iter().map(String::from).collect();
+pub(crate) fn prod_id(domain: &str, label: Option<String>) -> String {
+    let mut strings: Vec<String> = vec![String::from(PROD_ID), String::from(domain)];
     if let Some(label) = label {
         strings.splice(0..0, [label]);
     }
@@ -249,37 +663,305 @@ fn prod_id(label: Option<String>) -> String {
 
 /// Get a unique id for a specific waste collection type at a specific location.
 ///
-/// Changing this function is a breaking change!  
-fn uid(street: &str, street_number: &str, summary: &str) -> String {
+/// Changing this function or its input, the requested street and street number, is a breaking change!  
+fn uid(domain: &str, street: &str, street_number: &str, summary: &str) -> String {
     let whitespace_regex = Regex::new(r"\s+").unwrap();
     let whitespace_rep = "-";
     let street = whitespace_regex.replace_all(street, whitespace_rep);
     let street_number = whitespace_regex.replace_all(street_number, whitespace_rep);
     let summary = whitespace_regex.replace_all(summary, whitespace_rep);
-    format!("Abfuhrkalender_{street}_{street_number}_{summary}@karlsruhe.de")
+    format!("Abfuhrkalender_{street}_{street_number}_{summary}@{domain}")
 }
 
-/// This is the data which can be extracted from the official website.
+/// Get the time zone of the events, which every calendar referring to it by `TZID` has to define (RFC 5545).
+///
+/// The rules of the central European summer time, which apply since 1996, are sufficient for all dates of the calendars.
+pub(crate) fn timezone() -> IcalTimeZone {
+    let transition =
+        |transition, offset_from: &str, offset_to: &str, name: &str, start: &str, month: u32| {
+            let mut transition = IcalTimeZoneTransition::new(transition);
+            transition.properties = vec![
+                ical_property!("TZOFFSETFROM", offset_from),
+                ical_property!("TZOFFSETTO", offset_to),
+                ical_property!("TZNAME", name),
+                ical_property!("DTSTART", start),
+                ical_property!("RRULE", format!("FREQ=YEARLY;BYMONTH={month};BYDAY=-1SU")),
+            ];
+            transition
+        };
+    let mut timezone = IcalTimeZone::new();
+    timezone.properties = vec![ical_property!("TZID", TIMEZONE)];
+    timezone.transitions = vec![
+        transition(
+            IcalTimeZoneTransitionType::DAYLIGHT,
+            "+0100",
+            "+0200",
+            "CEST",
+            "19700329T020000",
+            3,
+        ),
+        transition(
+            IcalTimeZoneTransitionType::STANDARD,
+            "+0200",
+            "+0100",
+            "CET",
+            "19701025T030000",
+            10,
+        ),
+    ];
+    timezone
+}
+
+/// Check whether a unique id has been built by [`uid`] for an address, e.g. to find the events of an address in other calendars.
+pub fn is_uid_of_address(
+    value: &str,
+    provider: &dyn Provider,
+    street: &str,
+    street_number: &str,
+) -> bool {
+    let address_uid = uid(provider.domain(), street, street_number, "");
+    let (prefix, domain) = address_uid.split_once('@').unwrap();
+    value.starts_with(prefix) && value.ends_with(&format!("@{domain}"))
+}
+
+/// Get the waste type of a unique id built by [`uid`] from the labels of the waste types of a provider.
+///
+/// The label may be followed by a suffix, e.g. the assignee of a rotation, so the longest matching label wins.
+fn waste_type_of_uid(uid: &str, provider: &dyn Provider) -> Option<WasteType> {
+    let summary = uid
+        .strip_prefix("Abfuhrkalender_")?
+        .split('@')
+        .next()?
+        .rsplit('_')
+        .next()?;
+    let whitespace_regex = Regex::new(r"\s+").unwrap();
+    WasteType::ALL
+        .into_iter()
+        .map(|waste_type| {
+            let label = whitespace_regex.replace_all(provider.label(waste_type), "-");
+            (waste_type, label.into_owned())
+        })
+        .filter(|(_, label)| summary == label || summary.starts_with(&format!("{label}-")))
+        .max_by_key(|(_, label)| label.len())
+        .map(|(waste_type, _)| waste_type)
+}
+
+/// The official server did not resolve the requested address.
+///
+/// It either resolved another address, which is the canonical one if only the spelling differs, or none at all.
 #[derive(Debug, PartialEq)]
-struct WasteData {
+pub struct AddressMismatchError {
+    pub street: String,
+    pub street_number: String,
+    pub resolved_address: Option<(String, String)>,
+}
+
+impl fmt::Display for AddressMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let street = &self.street;
+        let street_number = &self.street_number;
+        match &self.resolved_address {
+            Some((resolved_street, resolved_street_number)) => write!(
+                f,
+                "the address \"{street} {street_number}\" was resolved to \"{resolved_street} {resolved_street_number}\""
+            ),
+            None => write!(f, "the address \"{street} {street_number}\" could not be resolved"),
+        }
+    }
+}
+
+impl Error for AddressMismatchError {}
+
+/// This is the data which can be extracted from the official website.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WasteData {
     pub residual_waste: Vec<NaiveDate>,
     pub organic_waste: Vec<NaiveDate>,
     pub recyclable_waste: Vec<NaiveDate>,
     pub paper_waste: Vec<NaiveDate>,
-    pub bulky_waste: Option<NaiveDate>,
+    pub bulky_waste: Vec<NaiveDate>,
+    pub hazardous_waste: Vec<NaiveDate>,
+    pub notices: Vec<Notice>,
+    /// The collection rhythms as announced by the official website.
+    pub rhythms: BTreeMap<WasteType, Rhythm>,
+    /// The notes next to single dates, e.g. "Fronleichnam am 08.06. wurde berücksichtigt".
+    pub notes: BTreeMap<(WasteType, NaiveDate), String>,
+    /// The predicted dates after the last published date, see [`crate::prediction::predict`].
+    pub predictions: BTreeMap<WasteType, Vec<NaiveDate>>,
+    /// The stops of the mobile hazardous waste collection whose dates are the hazardous waste dates.
+    pub stops: Vec<Stop>,
+    /// The problems which did not prevent reading the waste data, e.g. a schedule of the stops which cannot be read.
+    ///
+    /// They are left to the caller to report.
+    pub warnings: Vec<String>,
+}
+
+impl WasteData {
+    /// Get the dates of a specific waste type.
+    pub fn dates(&self, waste_type: WasteType) -> &[NaiveDate] {
+        match waste_type {
+            WasteType::Residual => &self.residual_waste,
+            WasteType::Organic => &self.organic_waste,
+            WasteType::Recyclable => &self.recyclable_waste,
+            WasteType::Paper => &self.paper_waste,
+            WasteType::Bulky => &self.bulky_waste,
+            WasteType::Hazardous => &self.hazardous_waste,
+        }
+    }
+
+    /// Check whether there are no collection dates of any waste type.
+    pub fn is_empty(&self) -> bool {
+        WasteType::ALL
+            .into_iter()
+            .all(|waste_type| self.dates(waste_type).is_empty())
+    }
+
+    /// Get the collection rhythm of a specific waste type.
+    ///
+    /// If the official website does not announce it, it is derived from the dates.
+    /// The bulky and the hazardous waste collections are irregular and have no rhythm.
+    pub fn rhythm(&self, waste_type: WasteType) -> Option<Rhythm> {
+        if matches!(waste_type, WasteType::Bulky | WasteType::Hazardous) {
+            return None;
+        }
+        self.rhythms
+            .get(&waste_type)
+            .copied()
+            .or_else(|| Rhythm::from_dates(self.dates(waste_type)))
+    }
+
+    /// Get the collections of a specific waste type, which tell whether they have been shifted.
+    pub fn collections(&self, waste_type: WasteType) -> Vec<Collection> {
+        let rhythm = self.rhythm(waste_type);
+        self.dates(waste_type)
+            .iter()
+            .map(|date| Collection {
+                waste_type,
+                date: *date,
+                shifted_from: rhythm
+                    .map(|rhythm| rhythm.regular_date(*date))
+                    .filter(|regular_date| regular_date != date),
+                note: self.notes.get(&(waste_type, *date)).cloned(),
+            })
+            .collect()
+    }
+
+    /// Get the mutable dates of a specific waste type.
+    pub fn dates_mut(&mut self, waste_type: WasteType) -> &mut Vec<NaiveDate> {
+        match waste_type {
+            WasteType::Residual => &mut self.residual_waste,
+            WasteType::Organic => &mut self.organic_waste,
+            WasteType::Recyclable => &mut self.recyclable_waste,
+            WasteType::Paper => &mut self.paper_waste,
+            WasteType::Bulky => &mut self.bulky_waste,
+            WasteType::Hazardous => &mut self.hazardous_waste,
+        }
+    }
+}
+
+/// A single collection of a waste type.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Collection {
+    pub waste_type: WasteType,
+    pub date: NaiveDate,
+    /// The regular date of the collection, if it deviates from the regular weekday, e.g. because of a holiday.
+    pub shifted_from: Option<NaiveDate>,
+    /// The note of the official website next to the date.
+    pub note: Option<String>,
+}
+
+impl Collection {
+    /// Describe why this collection has been shifted.
+    ///
+    /// The notes of the official website and the names of the holidays are always German.
+    pub fn shift_description(&self, locale: Locale) -> Option<String> {
+        let shifted_from = self.shifted_from?;
+        let weekday = locale.weekday(shifted_from.weekday());
+        let date = locale.format_date(shifted_from);
+        let (shifted, on) = match locale {
+            Locale::German => ("Verschoben vom", "am"),
+            Locale::English => ("Shifted from", "on"),
+        };
+        let reason = self.note.clone().or_else(|| {
+            holiday(shifted_from).map(|holiday| format!("{} {on} {date}", holiday.name))
+        });
+        Some(match reason {
+            Some(reason) => format!("{shifted} {weekday}, {date}: {reason}"),
+            None => format!("{shifted} {weekday}, {date}"),
+        })
+    }
+}
+
+/// A stop of the mobile hazardous waste collection.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Stop {
+    pub date: NaiveDate,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub district: String,
+    pub location: String,
+}
+
+impl Stop {
+    /// Describe the time window and the location of this stop.
+    pub fn describe(&self) -> String {
+        format!(
+            "{}–{} Uhr, {}, {}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M"),
+            self.location,
+            self.district
+        )
+    }
+}
+
+/// A notice of the official website, e.g. about collection changes because of a holiday.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notice {
+    pub text: String,
+    pub links: Vec<String>,
+}
+
+impl fmt::Display for Notice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = &self.text;
+        match self.links.is_empty() {
+            true => write!(f, "{text}"),
+            false => write!(f, "{text} ({})", self.links.join(" ")),
+        }
+    }
 }
 
 #[cfg(test)]
-mod tests {
-    use chrono::NaiveDate;
-    use ical::generator::{IcalCalendar, IcalEvent};
+pub(crate) mod tests {
+    use std::collections::BTreeMap;
 
-    use crate::garbage_client::{
-        get, get_calendar, parse, WasteData, WasteTypeBitmask, LABEL_BULKY, LABEL_ORGANIC,
-        LABEL_RECYCLABLE, LABEL_RESIDUAL,
+    use async_trait::async_trait;
+    use chrono::{NaiveDate, NaiveTime, Weekday};
+    use ical::generator::{Emitter, IcalCalendar, IcalEvent, Property};
+
+    use crate::{
+        address::Address,
+        garbage_client::{
+            get, get_calendar, is_same_calendar, is_uid_of_address, merge_calendar, parse_calendar,
+            Notice, Stop, WasteData, WasteType, WasteTypeBitmask, LABEL_BULKY, LABEL_HAZARDOUS,
+            LABEL_ORGANIC, LABEL_RECYCLABLE, LABEL_RESIDUAL,
+        },
+        locale::Locale,
+        prediction::Rhythm,
+        provider::{karlsruhe::KarlsruheProvider, Provider},
+        render::{
+            parse_alarms, parse_assignees, parse_blackouts, BlackoutMode, RenderOptions, Rotation,
+            RotationMode,
+        },
     };
 
-    fn get_test_waste_data() -> WasteData {
+    /// Get a date in 2023, the year of the test data.
+    pub(crate) fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, month, day).unwrap()
+    }
+
+    pub(crate) fn get_test_waste_data() -> WasteData {
         WasteData {
             residual_waste: vec![
                 NaiveDate::from_ymd_opt(2023, 6, 16).unwrap(),
@@ -301,7 +983,67 @@ mod tests {
                 NaiveDate::from_ymd_opt(2023, 7, 12).unwrap(),
                 NaiveDate::from_ymd_opt(2023, 8, 9).unwrap(),
             ],
-            bulky_waste: Some(NaiveDate::from_ymd_opt(2023, 7, 12).unwrap()),
+            bulky_waste: vec![NaiveDate::from_ymd_opt(2023, 7, 12).unwrap()],
+            hazardous_waste: vec![],
+            notices: vec![],
+            rhythms: BTreeMap::from([
+                (
+                    WasteType::Residual,
+                    Rhythm {
+                        weekday: Weekday::Fri,
+                        interval_weeks: 2,
+                    },
+                ),
+                (
+                    WasteType::Organic,
+                    Rhythm {
+                        weekday: Weekday::Wed,
+                        interval_weeks: 1,
+                    },
+                ),
+                (
+                    WasteType::Recyclable,
+                    Rhythm {
+                        weekday: Weekday::Thu,
+                        interval_weeks: 2,
+                    },
+                ),
+                (
+                    WasteType::Paper,
+                    Rhythm {
+                        weekday: Weekday::Wed,
+                        interval_weeks: 4,
+                    },
+                ),
+            ]),
+            notes: BTreeMap::from([
+                (
+                    (
+                        WasteType::Residual,
+                        NaiveDate::from_ymd_opt(2023, 6, 29).unwrap(),
+                    ),
+                    String::from(
+                        "Aus betrieblichen Gründen nicht im Einsatz am 30.06. wurde berücksichtigt",
+                    ),
+                ),
+                (
+                    (
+                        WasteType::Organic,
+                        NaiveDate::from_ymd_opt(2023, 6, 7).unwrap(),
+                    ),
+                    String::from("Fronleichnam am 08.06. wurde berücksichtigt"),
+                ),
+                (
+                    (
+                        WasteType::Recyclable,
+                        NaiveDate::from_ymd_opt(2023, 6, 7).unwrap(),
+                    ),
+                    String::from("Fronleichnam am 08.06. wurde berücksichtigt"),
+                ),
+            ]),
+            predictions: BTreeMap::new(),
+            stops: vec![],
+            warnings: vec![],
         }
     }
 
@@ -310,9 +1052,14 @@ mod tests {
     /// This is an online test!
     #[tokio::test]
     async fn test_get() {
-        let calendar = get("Schloßplatz", "1", WasteTypeBitmask::none())
-            .await
-            .unwrap();
+        let address = Address::new("Schloßplatz", "1").unwrap();
+        let calendar = get(
+            &KarlsruheProvider::default(),
+            &address,
+            WasteTypeBitmask::none(),
+        )
+        .await
+        .unwrap();
         assert!(!calendar.events.is_empty());
     }
 
@@ -347,7 +1094,14 @@ mod tests {
     #[test]
     fn test_get_calendar_all() {
         let waste_data = get_test_waste_data();
-        let calendar = get_calendar("street", "69", waste_data, WasteTypeBitmask::none());
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            waste_data,
+            WasteTypeBitmask::none(),
+            &RenderOptions::default(),
+        );
         assert_eq!(calendar.events.len(), 5);
         let residual_dtstart = get_property_value_of_event(&calendar, "DTSTART", LABEL_RESIDUAL);
         assert_eq!(residual_dtstart, "20230616");
@@ -358,17 +1112,26 @@ mod tests {
     #[test]
     fn test_get_calendar_exclusion() {
         let waste_data = get_test_waste_data();
-        let calendar = get_calendar("street", "69", waste_data, WasteTypeBitmask::Bulky);
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            waste_data,
+            WasteTypeBitmask::Bulky,
+            &RenderOptions::default(),
+        );
         assert_eq!(calendar.events.len(), 4);
         let bulky_found = find_event(&calendar, LABEL_BULKY).is_some();
         assert!(!bulky_found);
 
         let waste_data = get_test_waste_data();
         let calendar = get_calendar(
+            &KarlsruheProvider::default(),
             "street",
             "69",
             waste_data,
             WasteTypeBitmask::Recyclable | WasteTypeBitmask::Organic,
+            &RenderOptions::default(),
         );
         assert_eq!(calendar.events.len(), 3);
         let recyclable_found = find_event(&calendar, LABEL_RECYCLABLE).is_some();
@@ -377,14 +1140,648 @@ mod tests {
         assert!(!organic_found);
     }
 
-    /// Test whether the HTML is parsed correctly.
-    ///
-    /// This test is offline.
     #[test]
-    fn test_parse() {
-        let html = include_str!("garbage_client/tests/response.html");
-        let parsed = parse(html).unwrap();
-        let expected = get_test_waste_data();
-        assert_eq!(parsed, expected)
+    fn test_get_calendar_notices() {
+        let mut waste_data = get_test_waste_data();
+        waste_data.notices.push(Notice {
+            text: String::from("Wegen Fronleichnam, am 08.06."),
+            links: vec![String::from("https://www.karlsruhe.de")],
+        });
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            waste_data,
+            WasteTypeBitmask::none(),
+            &RenderOptions::default(),
+        );
+        let notice = calendar
+            .properties
+            .iter()
+            .find(|property| property.name == "X-KGC-NOTICE")
+            .and_then(|property| property.value.as_ref())
+            .unwrap();
+        assert_eq!(
+            notice,
+            "Wegen Fronleichnam\\, am 08.06. (https://www.karlsruhe.de)"
+        );
+    }
+
+    #[test]
+    fn test_get_calendar_predictions() {
+        let mut waste_data = get_test_waste_data();
+        waste_data.predictions.insert(
+            WasteType::Paper,
+            vec![NaiveDate::from_ymd_opt(2023, 9, 6).unwrap()],
+        );
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            waste_data,
+            WasteTypeBitmask::none(),
+            &RenderOptions::default(),
+        );
+        assert_eq!(calendar.events.len(), 6);
+        let prediction = calendar.events.last().unwrap();
+        let value = |name: &str| {
+            prediction
+                .properties
+                .iter()
+                .find(|property| property.name == name)
+                .and_then(|property| property.value.as_deref())
+        };
+        assert_eq!(value("STATUS"), Some("TENTATIVE"));
+        assert_eq!(
+            value("UID"),
+            Some("Abfuhrkalender_street_69_Papier-Prognose@karlsruhe.de")
+        );
+        assert_eq!(value("RDATE"), Some("20230906"));
+
+        let parsed = parse_calendar(&calendar.generate(), &KarlsruheProvider::default()).unwrap();
+        assert_eq!(parsed.paper_waste, get_test_waste_data().paper_waste);
+    }
+
+    /// Test whether a collection which is shifted into the previous week is shifted from its regular date.
+    #[test]
+    fn test_collections_pfingstmontag() {
+        let waste_data = WasteData {
+            paper_waste: vec![date(5, 15), date(5, 27), date(6, 12)],
+            rhythms: BTreeMap::from([(
+                WasteType::Paper,
+                Rhythm {
+                    weekday: Weekday::Mon,
+                    interval_weeks: 2,
+                },
+            )]),
+            ..WasteData::default()
+        };
+        let collections = waste_data.collections(WasteType::Paper);
+        assert_eq!(collections[0].shifted_from, None);
+        assert_eq!(collections[1].shifted_from, Some(date(5, 29)));
+        assert_eq!(
+            collections[1].shift_description(Locale::German),
+            Some(String::from(
+                "Verschoben vom Montag, 29.05.2023: Pfingstmontag am 29.05.2023"
+            ))
+        );
+        assert_eq!(collections[2].shifted_from, None);
+    }
+
+    #[test]
+    fn test_collections() {
+        let waste_data = get_test_waste_data();
+        let shifted_from = |waste_type: WasteType| -> Vec<Option<NaiveDate>> {
+            waste_data
+                .collections(waste_type)
+                .into_iter()
+                .map(|collection| collection.shifted_from)
+                .collect()
+        };
+        assert_eq!(
+            shifted_from(WasteType::Residual),
+            vec![None, NaiveDate::from_ymd_opt(2023, 6, 30), None]
+        );
+        assert_eq!(shifted_from(WasteType::Organic), vec![None, None, None]);
+        assert_eq!(
+            shifted_from(WasteType::Recyclable),
+            vec![NaiveDate::from_ymd_opt(2023, 6, 8), None, None]
+        );
+        assert_eq!(shifted_from(WasteType::Bulky), vec![None]);
+        assert_eq!(
+            waste_data.collections(WasteType::Recyclable)[0].shift_description(Locale::German),
+            Some(String::from(
+                "Verschoben vom Donnerstag, 08.06.2023: Fronleichnam am 08.06. wurde berücksichtigt"
+            ))
+        );
+        assert_eq!(
+            waste_data.collections(WasteType::Residual)[1].shift_description(Locale::English),
+            Some(String::from(
+                "Shifted from Friday, 2023-06-30: Aus betrieblichen Gründen nicht im Einsatz am 30.06. wurde berücksichtigt"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_calendar_shifted() {
+        let waste_data = get_test_waste_data();
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            waste_data,
+            WasteTypeBitmask::none(),
+            &RenderOptions {
+                mark_shifted: true,
+                ..RenderOptions::default()
+            },
+        );
+        assert_eq!(calendar.events.len(), 7);
+        let recyclable_rdate = get_property_value_of_event(&calendar, "RDATE", LABEL_RECYCLABLE);
+        assert_eq!(recyclable_rdate, "20230622,20230706");
+        let summary = format!("{LABEL_RECYCLABLE} ⚠ verschoben");
+        assert_eq!(
+            get_property_value_of_event(&calendar, "DTSTART", &summary),
+            "20230607"
+        );
+        assert_eq!(
+            get_property_value_of_event(&calendar, "DESCRIPTION", &summary),
+            "Verschoben vom Donnerstag\\, 08.06.2023: Fronleichnam am 08.06. wurde berücksichtigt\\nhttps://web6.karlsruhe.de/service/abfall/akal/akal.php"
+        );
+
+        let parsed = parse_calendar(&calendar.generate(), &KarlsruheProvider::default()).unwrap();
+        assert_eq!(parsed.residual_waste, get_test_waste_data().residual_waste);
+        assert_eq!(
+            parsed.recyclable_waste,
+            get_test_waste_data().recyclable_waste
+        );
+    }
+
+    #[test]
+    fn test_get_calendar_window() {
+        let options = RenderOptions {
+            from: NaiveDate::from_ymd_opt(2023, 6, 10),
+            to: NaiveDate::from_ymd_opt(2023, 7, 10),
+            blackouts: parse_blackouts("2023-06-20..2023-06-30").unwrap(),
+            ..RenderOptions::default()
+        };
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            get_test_waste_data(),
+            WasteTypeBitmask::none(),
+            &options,
+        );
+        assert_eq!(calendar.events.len(), 4);
+        assert!(find_event(&calendar, LABEL_BULKY).is_none());
+        let parsed = parse_calendar(&calendar.generate(), &KarlsruheProvider::default()).unwrap();
+        assert_eq!(
+            parsed.residual_waste,
+            vec![NaiveDate::from_ymd_opt(2023, 6, 16).unwrap()]
+        );
+        assert_eq!(
+            parsed.recyclable_waste,
+            vec![NaiveDate::from_ymd_opt(2023, 7, 6).unwrap()]
+        );
+
+        let options = RenderOptions {
+            blackout_mode: BlackoutMode::Mark,
+            ..options
+        };
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            get_test_waste_data(),
+            WasteTypeBitmask::none(),
+            &options,
+        );
+        assert_eq!(calendar.events.len(), 7);
+        let summary = format!("{LABEL_RESIDUAL} ✈ abwesend");
+        assert_eq!(
+            get_property_value_of_event(&calendar, "RDATE", &summary),
+            "20230629"
+        );
+        assert_eq!(
+            get_property_value_of_event(&calendar, "DESCRIPTION", &summary),
+            "Abwesend vom 20.06.2023 bis 30.06.2023\\nhttps://web6.karlsruhe.de/service/abfall/akal/akal.php"
+        );
+        let parsed = parse_calendar(&calendar.generate(), &KarlsruheProvider::default()).unwrap();
+        assert_eq!(
+            parsed.residual_waste,
+            vec![
+                NaiveDate::from_ymd_opt(2023, 6, 16).unwrap(),
+                NaiveDate::from_ymd_opt(2023, 6, 29).unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_calendar_rotation() {
+        let options = RenderOptions {
+            rotation: Some(Rotation {
+                assignees: parse_assignees("Anna, Ben <ben@example.org>").unwrap(),
+                mode: RotationMode::Collection,
+                start: NaiveDate::from_ymd_opt(2023, 6, 12),
+            }),
+            ..RenderOptions::default()
+        };
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            get_test_waste_data(),
+            WasteTypeBitmask::none(),
+            &options,
+        );
+        // the shifted residual collection on 29.06. keeps the assignee of its regular date 30.06.
+        let summary = format!("{LABEL_RESIDUAL} – Anna");
+        assert_eq!(
+            get_property_value_of_event(&calendar, "RDATE", &summary),
+            "20230616,20230714"
+        );
+        assert_eq!(
+            get_property_value_of_event(&calendar, "UID", &summary),
+            "Abfuhrkalender_street_69_Restmüll-Anna@karlsruhe.de"
+        );
+        let summary = format!("{LABEL_RESIDUAL} – Ben");
+        assert_eq!(
+            get_property_value_of_event(&calendar, "RDATE", &summary),
+            "20230629"
+        );
+        let attendee = find_event(&calendar, &summary)
+            .unwrap()
+            .properties
+            .iter()
+            .find(|property| property.name == "ATTENDEE")
+            .unwrap();
+        assert_eq!(attendee.value.as_deref(), Some("mailto:ben@example.org"));
+        assert_eq!(
+            attendee.params,
+            Some(vec![(String::from("CN"), vec![String::from("Ben")])])
+        );
+
+        let parsed = parse_calendar(&calendar.generate(), &KarlsruheProvider::default()).unwrap();
+        assert_eq!(parsed.residual_waste, get_test_waste_data().residual_waste);
+    }
+
+    #[test]
+    fn test_get_calendar_alarms_and_time_of_day() {
+        let options = RenderOptions {
+            alarms: parse_alarms("1d,6h").unwrap(),
+            time_of_day: NaiveTime::from_hms_opt(6, 0, 0),
+            ..RenderOptions::default()
+        };
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            get_test_waste_data(),
+            WasteTypeBitmask::none(),
+            &options,
+        );
+        let event = find_event(&calendar, LABEL_RESIDUAL).unwrap();
+        let property = |name| {
+            event
+                .properties
+                .iter()
+                .find(|property| property.name == name)
+                .unwrap()
+        };
+        assert_eq!(
+            property("DTSTART").value.as_deref(),
+            Some("20230616T060000")
+        );
+        assert_eq!(property("DURATION").value.as_deref(), Some("PT1H"));
+        assert_eq!(
+            property("RDATE").value.as_deref(),
+            Some("20230616T060000,20230629T060000,20230714T060000")
+        );
+        assert_eq!(
+            property("RDATE").params,
+            Some(vec![(
+                String::from("TZID"),
+                vec![String::from("Europe/Berlin")]
+            )])
+        );
+        let triggers: Vec<&str> = event
+            .alarms
+            .iter()
+            .flat_map(|alarm| &alarm.properties)
+            .filter(|property| property.name == "TRIGGER")
+            .filter_map(|property| property.value.as_deref())
+            .collect();
+        assert_eq!(triggers, vec!["-P1D", "-PT6H"]);
+        assert!(calendar
+            .generate()
+            .contains("BEGIN:VALARM\r\nACTION:DISPLAY"));
+        let generated = calendar.generate();
+        assert!(generated.contains("BEGIN:VTIMEZONE\r\nTZID:Europe/Berlin\r\nBEGIN:DAYLIGHT"));
+        let timezones = ical::IcalParser::new(generated.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap()
+            .timezones;
+        assert_eq!(timezones.len(), 1);
+        assert_eq!(timezones[0].transitions.len(), 2);
+
+        let parsed = parse_calendar(&calendar.generate(), &KarlsruheProvider::default()).unwrap();
+        assert_eq!(parsed.residual_waste, get_test_waste_data().residual_waste);
+    }
+
+    /// Test whether the assignees stay the same when the inferred interval of a waste type changes between two fetches.
+    #[test]
+    fn test_get_calendar_rotation_with_changing_interval() {
+        let options = RenderOptions {
+            rotation: Some(Rotation {
+                assignees: parse_assignees("Anna, Ben").unwrap(),
+                mode: RotationMode::Collection,
+                start: NaiveDate::from_ymd_opt(2023, 6, 12),
+            }),
+            ..RenderOptions::default()
+        };
+        let rdates = |residual_waste: Vec<NaiveDate>| {
+            let waste_data = WasteData {
+                residual_waste,
+                ..WasteData::default()
+            };
+            // biweekly at first, weekly as soon as the last date has been published
+            assert!(waste_data.rhythm(WasteType::Residual).is_some());
+            let calendar = get_calendar(
+                &KarlsruheProvider::default(),
+                "street",
+                "69",
+                waste_data,
+                WasteTypeBitmask::none(),
+                &options,
+            );
+            ["Anna", "Ben"].map(|name| {
+                get_property_value_of_event(
+                    &calendar,
+                    "RDATE",
+                    &format!("{LABEL_RESIDUAL} – {name}"),
+                )
+                .to_string()
+            })
+        };
+        assert_eq!(
+            rdates(vec![date(6, 16), date(6, 30), date(7, 14)]),
+            ["20230616,20230714", "20230630"]
+        );
+        assert_eq!(
+            rdates(vec![date(6, 16), date(6, 30), date(7, 14), date(7, 21)]),
+            ["20230616,20230714", "20230630,20230721"]
+        );
+    }
+
+    #[test]
+    fn test_get_calendar_stops() {
+        let date = NaiveDate::from_ymd_opt(2023, 6, 10).unwrap();
+        let waste_data = WasteData {
+            hazardous_waste: vec![date],
+            stops: vec![Stop {
+                date,
+                start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+                district: String::from("Durlach"),
+                location: String::from("Festplatz, Am Zwinger"),
+            }],
+            ..get_test_waste_data()
+        };
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            waste_data.clone(),
+            WasteTypeBitmask::none(),
+            &RenderOptions::default(),
+        );
+        assert_eq!(calendar.events.len(), 6);
+        assert_eq!(
+            get_property_value_of_event(&calendar, "DTSTART", LABEL_HAZARDOUS),
+            "20230610T090000"
+        );
+        assert_eq!(
+            get_property_value_of_event(&calendar, "DTEND", LABEL_HAZARDOUS),
+            "20230610T120000"
+        );
+        assert_eq!(
+            get_property_value_of_event(&calendar, "LOCATION", LABEL_HAZARDOUS),
+            "Festplatz\\, Am Zwinger\\, Durlach"
+        );
+        let parsed = parse_calendar(&calendar.generate(), &KarlsruheProvider::default()).unwrap();
+        assert_eq!(parsed.hazardous_waste, vec![date]);
+
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            waste_data,
+            WasteTypeBitmask::Hazardous,
+            &RenderOptions::default(),
+        );
+        assert!(find_event(&calendar, LABEL_HAZARDOUS).is_none());
+    }
+
+    #[test]
+    fn test_parse_calendar() {
+        let waste_data = get_test_waste_data();
+        let calendar = get_calendar(
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+            waste_data,
+            WasteTypeBitmask::Bulky,
+            &RenderOptions::default(),
+        );
+        let parsed = parse_calendar(&calendar.generate(), &KarlsruheProvider::default()).unwrap();
+        let expected = WasteData {
+            bulky_waste: vec![],
+            notices: vec![],
+            rhythms: BTreeMap::new(),
+            notes: BTreeMap::new(),
+            ..get_test_waste_data()
+        };
+        assert_eq!(parsed, expected);
+    }
+
+    /// A provider whose official website uses other labels, with spaces, for some waste types.
+    #[derive(Debug, Default)]
+    struct RelabellingProvider(KarlsruheProvider);
+
+    #[async_trait]
+    impl Provider for RelabellingProvider {
+        fn id(&self) -> &'static str {
+            "relabelling"
+        }
+
+        fn municipality(&self) -> &'static str {
+            "Relabelling"
+        }
+
+        fn domain(&self) -> &'static str {
+            "relabelling.example.org"
+        }
+
+        fn url(&self) -> &str {
+            self.0.url()
+        }
+
+        fn label(&self, waste_type: WasteType) -> &'static str {
+            match waste_type {
+                WasteType::Residual => "Graue Tonne",
+                WasteType::Organic => "Grüne Tonne",
+                _ => waste_type.label(),
+            }
+        }
+
+        async fn get_streets(&self) -> anyhow::Result<Vec<String>> {
+            self.0.get_streets().await
+        }
+
+        async fn get_response(&self, address: &Address) -> anyhow::Result<String> {
+            self.0.get_response(address).await
+        }
+
+        fn parse_response(&self, address: &Address, response: &str) -> anyhow::Result<WasteData> {
+            self.0.parse_response(address, response)
+        }
+    }
+
+    /// Test whether the waste types of a calendar are parsed by the labels of the provider which built it.
+    #[test]
+    fn test_parse_calendar_relabelling_provider() {
+        let provider = RelabellingProvider::default();
+        let options = RenderOptions {
+            rotation: Some(Rotation {
+                assignees: parse_assignees("Anna").unwrap(),
+                mode: RotationMode::Collection,
+                start: None,
+            }),
+            ..RenderOptions::default()
+        };
+        for options in [RenderOptions::default(), options] {
+            let calendar = get_calendar(
+                &provider,
+                "street",
+                "69",
+                get_test_waste_data(),
+                WasteTypeBitmask::Bulky,
+                &options,
+            );
+            let parsed = parse_calendar(&calendar.generate(), &provider).unwrap();
+            assert_eq!(parsed.residual_waste, get_test_waste_data().residual_waste);
+            assert_eq!(parsed.organic_waste, get_test_waste_data().organic_waste);
+            assert_eq!(parsed.paper_waste, get_test_waste_data().paper_waste);
+        }
+    }
+
+    #[test]
+    fn test_is_same_calendar() {
+        let calendar = |waste_data| {
+            get_calendar(
+                &KarlsruheProvider::default(),
+                "street",
+                "69",
+                waste_data,
+                WasteTypeBitmask::none(),
+                &RenderOptions::default(),
+            )
+            .generate()
+        };
+        let old = calendar(get_test_waste_data());
+        let restamped = old
+            .lines()
+            .map(|line| {
+                if line.starts_with("DTSTAMP") {
+                    "DTSTAMP;TZID=Europe/Berlin:20000101T000000"
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<&str>>()
+            .join("\r\n");
+        assert_ne!(old, restamped);
+        assert!(is_same_calendar(&old, &restamped));
+        let mut waste_data = get_test_waste_data();
+        waste_data.paper_waste.pop();
+        assert!(!is_same_calendar(&old, &calendar(waste_data)));
+    }
+
+    #[test]
+    fn test_merge_calendar() {
+        let calendar = |street_number, waste_data| {
+            get_calendar(
+                &KarlsruheProvider::default(),
+                "street",
+                street_number,
+                waste_data,
+                WasteTypeBitmask::none(),
+                &RenderOptions::default(),
+            )
+        };
+        let mut existing = calendar("69", get_test_waste_data());
+        let mut other_waste_data = get_test_waste_data();
+        other_waste_data.paper_waste.clear();
+        existing
+            .events
+            .extend(calendar("6", other_waste_data).events);
+        existing.events.push(IcalEvent {
+            properties: vec![
+                Property {
+                    name: String::from("UID"),
+                    value: Some(String::from("birthday@example.org")),
+                    params: None,
+                },
+                Property {
+                    name: String::from("SUMMARY"),
+                    value: Some(String::from("Birthday")),
+                    params: None,
+                },
+            ],
+            alarms: vec![],
+        });
+        let mut waste_data = get_test_waste_data();
+        waste_data.paper_waste.pop();
+        let merged = merge_calendar(
+            &existing.generate(),
+            calendar("69", waste_data.clone()),
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+        )
+        .unwrap();
+        assert!(find_event(&merged, "Birthday").is_some());
+        assert_eq!(merged.timezones.len(), 1);
+        assert!(merged
+            .events
+            .iter()
+            .flat_map(|event| &event.properties)
+            .any(|property| property.name == "UID"
+                && property
+                    .value
+                    .as_ref()
+                    .is_some_and(|value| value.contains("_6_"))));
+        assert_eq!(
+            parse_calendar(&merged.generate(), &KarlsruheProvider::default())
+                .unwrap()
+                .paper_waste,
+            waste_data.paper_waste
+        );
+        let only_merged = merge_calendar(
+            "",
+            calendar("69", waste_data),
+            &KarlsruheProvider::default(),
+            "street",
+            "69",
+        )
+        .unwrap();
+        assert_eq!(
+            parse_calendar(&only_merged.generate(), &KarlsruheProvider::default())
+                .unwrap()
+                .paper_waste
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_is_uid_of_address() {
+        let provider = KarlsruheProvider::default();
+        let is_uid = |value| is_uid_of_address(value, &provider, "Kaiser straße", "1");
+        assert!(is_uid(
+            "Abfuhrkalender_Kaiser-straße_1_Restmüll@karlsruhe.de"
+        ));
+        assert!(is_uid(
+            "Abfuhrkalender_Kaiser-straße_1_Bioabfall-Prognose@karlsruhe.de"
+        ));
+        assert!(!is_uid(
+            "Abfuhrkalender_Kaiser-straße_12_Restmüll@karlsruhe.de"
+        ));
+        assert!(!is_uid(
+            "Abfuhrkalender_Kaiser-straße_1_Restmüll@example.org"
+        ));
+        assert!(!is_uid("birthday@example.org"));
     }
 }
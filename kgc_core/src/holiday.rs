@@ -0,0 +1,118 @@
+//! This module calculates the public holidays of Baden-Württemberg.
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+/// A public holiday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: &'static str,
+}
+
+/// Get the public holidays of Baden-Württemberg in a specific year.
+pub fn holidays(year: i32) -> Vec<Holiday> {
+    let date = |month: u32, day: u32| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+    let easter = easter(year);
+    let mut holidays = vec![
+        Holiday {
+            date: date(1, 1),
+            name: "Neujahr",
+        },
+        Holiday {
+            date: date(1, 6),
+            name: "Heilige Drei Könige",
+        },
+        Holiday {
+            date: easter - Duration::days(2),
+            name: "Karfreitag",
+        },
+        Holiday {
+            date: easter + Duration::days(1),
+            name: "Ostermontag",
+        },
+        Holiday {
+            date: date(5, 1),
+            name: "Tag der Arbeit",
+        },
+        Holiday {
+            date: easter + Duration::days(39),
+            name: "Christi Himmelfahrt",
+        },
+        Holiday {
+            date: easter + Duration::days(50),
+            name: "Pfingstmontag",
+        },
+        Holiday {
+            date: easter + Duration::days(60),
+            name: "Fronleichnam",
+        },
+        Holiday {
+            date: date(10, 3),
+            name: "Tag der Deutschen Einheit",
+        },
+        Holiday {
+            date: date(11, 1),
+            name: "Allerheiligen",
+        },
+        Holiday {
+            date: date(12, 25),
+            name: "1. Weihnachtstag",
+        },
+        Holiday {
+            date: date(12, 26),
+            name: "2. Weihnachtstag",
+        },
+    ];
+    holidays.sort_by_key(|holiday| holiday.date);
+    holidays
+}
+
+/// Get the public holiday on a specific date.
+pub fn holiday(date: NaiveDate) -> Option<Holiday> {
+    holidays(date.year())
+        .into_iter()
+        .find(|holiday| holiday.date == date)
+}
+
+/// Calculate Easter Sunday with the anonymous Gregorian algorithm.
+fn easter(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::holiday::{easter, holiday, holidays};
+
+    #[test]
+    fn test_easter() {
+        assert_eq!(easter(2023), NaiveDate::from_ymd_opt(2023, 4, 9).unwrap());
+        assert_eq!(easter(2024), NaiveDate::from_ymd_opt(2024, 3, 31).unwrap());
+        assert_eq!(easter(2025), NaiveDate::from_ymd_opt(2025, 4, 20).unwrap());
+    }
+
+    #[test]
+    fn test_holidays() {
+        assert_eq!(holidays(2023).len(), 12);
+        let fronleichnam = holiday(NaiveDate::from_ymd_opt(2023, 6, 8).unwrap());
+        assert_eq!(fronleichnam.unwrap().name, "Fronleichnam");
+        let christi_himmelfahrt = holiday(NaiveDate::from_ymd_opt(2024, 5, 9).unwrap());
+        assert_eq!(christi_himmelfahrt.unwrap().name, "Christi Himmelfahrt");
+        assert!(holiday(NaiveDate::from_ymd_opt(2023, 6, 7).unwrap()).is_none());
+    }
+}
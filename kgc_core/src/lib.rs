@@ -5,4 +5,17 @@
 
 pub use ical;
 
+pub mod address;
+pub mod agenda;
+pub mod archive;
+pub mod diff;
+pub mod facility;
+pub mod file;
 pub mod garbage_client;
+pub mod holiday;
+pub mod locale;
+pub mod official_calendar;
+pub mod prediction;
+pub mod provider;
+pub mod render;
+pub mod source;
@@ -0,0 +1,79 @@
+//! This module provides the locales in which human-readable texts are written.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::garbage_client::WasteType;
+
+/// A locale for human-readable texts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "de")]
+    German,
+    #[serde(rename = "en")]
+    English,
+}
+
+impl Locale {
+    /// Format a date in the usual notation of this locale.
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        match self {
+            Locale::German => date.format("%d.%m.%Y").to_string(),
+            Locale::English => date.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    /// Get the name of a weekday.
+    pub fn weekday(&self, weekday: Weekday) -> &'static str {
+        let (german, english) = match weekday {
+            Weekday::Mon => ("Montag", "Monday"),
+            Weekday::Tue => ("Dienstag", "Tuesday"),
+            Weekday::Wed => ("Mittwoch", "Wednesday"),
+            Weekday::Thu => ("Donnerstag", "Thursday"),
+            Weekday::Fri => ("Freitag", "Friday"),
+            Weekday::Sat => ("Samstag", "Saturday"),
+            Weekday::Sun => ("Sonntag", "Sunday"),
+        };
+        match self {
+            Locale::German => german,
+            Locale::English => english,
+        }
+    }
+
+    /// Get the name of a waste type.
+    pub fn waste_type(&self, waste_type: WasteType) -> &'static str {
+        match (self, waste_type) {
+            (Locale::German, waste_type) => waste_type.label(),
+            (Locale::English, WasteType::Residual) => "Residual waste",
+            (Locale::English, WasteType::Organic) => "Organic waste",
+            (Locale::English, WasteType::Recyclable) => "Recyclable waste",
+            (Locale::English, WasteType::Paper) => "Paper",
+            (Locale::English, WasteType::Bulky) => "Bulky waste collection",
+            (Locale::English, WasteType::Hazardous) => "Hazardous waste collection",
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "de" | "de-de" | "german" | "deutsch" => Ok(Locale::German),
+            "en" | "en-gb" | "en-us" | "english" => Ok(Locale::English),
+            _ => Err(format!("the locale \"{value}\" is not supported")),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Locale::German => write!(f, "de"),
+            Locale::English => write!(f, "en"),
+        }
+    }
+}
@@ -0,0 +1,450 @@
+//! This module reads the official iCalendar download of the city and reconciles it with the scraped waste data.
+//!
+//! The official download consists of recurring rules which do not regard exceptions like holidays.
+//! Therefore the scraped dates should be preferred, the official download only reaches further into the future.
+
+use std::{collections::BTreeSet, fmt, fs, str::FromStr};
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use ical::IcalParser;
+use serde::Serialize;
+
+use crate::{
+    garbage_client::{WasteData, WasteType},
+    locale::Locale,
+};
+
+static FORMAT: &str = "%Y%m%d";
+
+/// The strategy to merge the scraped waste data with the official waste data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Use the scraped dates and fill the dates after the last scraped date of each waste type from the official download.
+    #[default]
+    PreferScraped,
+    /// Only use the scraped dates.
+    Scraped,
+    /// Only use the official dates.
+    Official,
+}
+
+impl FromStr for MergeStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "prefer-scraped" => Ok(MergeStrategy::PreferScraped),
+            "scraped" => Ok(MergeStrategy::Scraped),
+            "official" => Ok(MergeStrategy::Official),
+            _ => Err(format!("the merge strategy \"{value}\" is not supported")),
+        }
+    }
+}
+
+impl fmt::Display for MergeStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeStrategy::PreferScraped => write!(f, "prefer-scraped"),
+            MergeStrategy::Scraped => write!(f, "scraped"),
+            MergeStrategy::Official => write!(f, "official"),
+        }
+    }
+}
+
+/// A date on which the scraped waste data and the official waste data disagree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum Disagreement {
+    OnlyScraped {
+        waste_type: WasteType,
+        date: NaiveDate,
+    },
+    OnlyOfficial {
+        waste_type: WasteType,
+        date: NaiveDate,
+    },
+}
+
+impl Disagreement {
+    /// Describe this disagreement in a human-readable way.
+    pub fn describe(&self, locale: Locale) -> String {
+        match self {
+            Disagreement::OnlyScraped { waste_type, date } => {
+                let waste_type = locale.waste_type(*waste_type);
+                let date = locale.format_date(*date);
+                match locale {
+                    Locale::German => format!("{waste_type}: {date} nur auf der Website"),
+                    Locale::English => format!("{waste_type}: {date} only on the website"),
+                }
+            }
+            Disagreement::OnlyOfficial { waste_type, date } => {
+                let waste_type = locale.waste_type(*waste_type);
+                let date = locale.format_date(*date);
+                match locale {
+                    Locale::German => {
+                        format!("{waste_type}: {date} nur im offiziellen Kalender")
+                    }
+                    Locale::English => {
+                        format!("{waste_type}: {date} only in the official calendar")
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Get the official waste data from a URL or a file path.
+///
+/// Recurring rules are expanded until a specific date.
+pub async fn get(location: &str, until: NaiveDate) -> Result<WasteData> {
+    let ics = if location.starts_with("http://") || location.starts_with("https://") {
+        reqwest::get(location).await?.text().await?
+    } else {
+        fs::read_to_string(location)?
+    };
+    parse(&ics, until)
+}
+
+/// Parse the official iCalendar download to waste data.
+///
+/// The waste type of an event is determined by its summary, events of other waste types are skipped.
+pub fn parse(ics: &str, until: NaiveDate) -> Result<WasteData> {
+    let mut waste_data = WasteData::default();
+    for calendar in IcalParser::new(ics.as_bytes()) {
+        for event in calendar?.events {
+            let values = |name: &'static str| {
+                event
+                    .properties
+                    .iter()
+                    .filter(move |property| property.name == name)
+                    .filter_map(|property| property.value.as_deref())
+            };
+            let Some(waste_type) = values("SUMMARY").find_map(|summary| {
+                WasteType::ALL
+                    .into_iter()
+                    .find(|waste_type| summary.contains(waste_type.label()))
+            }) else {
+                continue;
+            };
+            let Some(start) = values("DTSTART").find_map(parse_date) else {
+                continue;
+            };
+            let mut dates = match values("RRULE").next() {
+                Some(rule) => expand(start, rule, until)?,
+                None => vec![start],
+            };
+            dates.extend(
+                values("RDATE")
+                    .flat_map(|value| value.split(','))
+                    .filter_map(parse_date),
+            );
+            let exception_dates: BTreeSet<NaiveDate> = values("EXDATE")
+                .flat_map(|value| value.split(','))
+                .filter_map(parse_date)
+                .collect();
+            let waste_type_dates = waste_data.dates_mut(waste_type);
+            waste_type_dates.extend(
+                dates
+                    .into_iter()
+                    .filter(|date| *date <= until && !exception_dates.contains(date)),
+            );
+            waste_type_dates.sort();
+            waste_type_dates.dedup();
+        }
+    }
+    Ok(waste_data)
+}
+
+/// Report the dates on which the scraped waste data and the official waste data disagree.
+///
+/// Only the period of the scraped dates is compared per waste type, because the official download reaches further.
+pub fn reconcile(scraped: &WasteData, official: &WasteData) -> Vec<Disagreement> {
+    let mut disagreements = vec![];
+    for waste_type in WasteType::ALL {
+        let scraped_dates: BTreeSet<NaiveDate> =
+            scraped.dates(waste_type).iter().copied().collect();
+        let official_dates: BTreeSet<NaiveDate> =
+            official.dates(waste_type).iter().copied().collect();
+        let (Some(first), Some(last)) = (scraped_dates.first(), scraped_dates.last()) else {
+            continue;
+        };
+        let period = *first..=*last;
+        disagreements.extend(scraped_dates.difference(&official_dates).map(|date| {
+            Disagreement::OnlyScraped {
+                waste_type,
+                date: *date,
+            }
+        }));
+        disagreements.extend(
+            official_dates
+                .difference(&scraped_dates)
+                .filter(|date| period.contains(date))
+                .map(|date| Disagreement::OnlyOfficial {
+                    waste_type,
+                    date: *date,
+                }),
+        );
+    }
+    disagreements
+}
+
+/// Merge the scraped waste data with the official waste data.
+///
+/// The notices, rhythms and notes are always taken from the scraped waste data.
+pub fn merge(scraped: WasteData, official: WasteData, strategy: MergeStrategy) -> WasteData {
+    let mut waste_data = scraped;
+    for waste_type in WasteType::ALL {
+        let official_dates = official.dates(waste_type);
+        let dates = waste_data.dates_mut(waste_type);
+        match strategy {
+            MergeStrategy::PreferScraped => {
+                let last = dates.iter().max().copied();
+                dates.extend(
+                    official_dates
+                        .iter()
+                        .filter(|date| last.map_or(true, |last| **date > last)),
+                );
+            }
+            MergeStrategy::Scraped => {}
+            MergeStrategy::Official => *dates = official_dates.to_vec(),
+        }
+        dates.sort();
+        dates.dedup();
+    }
+    waste_data
+}
+
+/// Parse a date or date-time value of an iCalendar property.
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, FORMAT).ok()
+}
+
+/// Expand a recurrence rule as defined in RFC 5545 until a specific date.
+///
+/// Only the daily and weekly frequencies are supported, which are the ones used for collections.
+fn expand(start: NaiveDate, rule: &str, until: NaiveDate) -> Result<Vec<NaiveDate>> {
+    let mut frequency = None;
+    let mut interval = 1;
+    let mut count = None;
+    let mut until = until;
+    let mut weekdays = vec![];
+    for part in rule.split(';') {
+        let Some((name, value)) = part.split_once('=') else {
+            bail!("the recurrence rule part \"{part}\" is invalid");
+        };
+        match name {
+            "FREQ" => frequency = Some(value),
+            "INTERVAL" => interval = value.parse()?,
+            "COUNT" => count = Some(value.parse::<usize>()?),
+            "UNTIL" => {
+                let Some(rule_until) = parse_date(value) else {
+                    bail!("the recurrence end \"{value}\" is invalid");
+                };
+                until = until.min(rule_until);
+            }
+            "BYDAY" => {
+                for weekday in value.split(',') {
+                    weekdays.push(match weekday {
+                        "MO" => Weekday::Mon,
+                        "TU" => Weekday::Tue,
+                        "WE" => Weekday::Wed,
+                        "TH" => Weekday::Thu,
+                        "FR" => Weekday::Fri,
+                        "SA" => Weekday::Sat,
+                        "SU" => Weekday::Sun,
+                        _ => bail!("the recurrence weekday \"{weekday}\" is not supported"),
+                    });
+                }
+            }
+            "WKST" => {}
+            _ => bail!("the recurrence rule part \"{name}\" is not supported"),
+        }
+    }
+    if interval < 1 {
+        bail!("the recurrence interval \"{interval}\" is invalid");
+    }
+    if weekdays.is_empty() {
+        weekdays.push(start.weekday());
+    }
+    weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+    let mut dates = vec![];
+    let mut step = 0;
+    loop {
+        let candidates = match frequency {
+            Some("DAILY") => vec![start + Duration::days(step * interval)],
+            Some("WEEKLY") => {
+                let week = start - Duration::days(start.weekday().num_days_from_monday().into())
+                    + Duration::weeks(step * interval);
+                weekdays
+                    .iter()
+                    .map(|weekday| week + Duration::days(weekday.num_days_from_monday().into()))
+                    .filter(|date| *date >= start)
+                    .collect()
+            }
+            Some(frequency) => bail!("the recurrence frequency \"{frequency}\" is not supported"),
+            None => bail!("the recurrence rule \"{rule}\" has no frequency"),
+        };
+        for date in candidates {
+            if date > until || count.is_some_and(|count| dates.len() >= count) {
+                return Ok(dates);
+            }
+            dates.push(date);
+        }
+        step += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        garbage_client::{tests::date, WasteData, WasteType},
+        official_calendar::{expand, merge, parse, reconcile, Disagreement, MergeStrategy},
+    };
+
+    fn get_scraped_waste_data() -> WasteData {
+        WasteData {
+            residual_waste: vec![date(6, 16), date(6, 29), date(7, 14)],
+            recyclable_waste: vec![date(6, 7), date(6, 22), date(7, 6)],
+            ..WasteData::default()
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(
+            expand(
+                date(6, 5),
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH",
+                date(6, 30)
+            )
+            .unwrap(),
+            vec![date(6, 5), date(6, 8), date(6, 19), date(6, 22)]
+        );
+        assert_eq!(
+            expand(date(6, 8), "FREQ=WEEKLY;BYDAY=MO,TH;COUNT=3", date(12, 31)).unwrap(),
+            vec![date(6, 8), date(6, 12), date(6, 15)]
+        );
+        assert_eq!(
+            expand(
+                date(6, 1),
+                "FREQ=DAILY;INTERVAL=10;UNTIL=20230625",
+                date(12, 31)
+            )
+            .unwrap(),
+            vec![date(6, 1), date(6, 11), date(6, 21)]
+        );
+        assert!(expand(date(6, 1), "FREQ=MONTHLY", date(12, 31)).is_err());
+        assert!(expand(date(6, 1), "FREQ=DAILY;INTERVAL=0", date(12, 31)).is_err());
+        assert!(expand(date(6, 1), "FREQ=WEEKLY;INTERVAL=-1", date(12, 31)).is_err());
+    }
+
+    /// Test whether the official iCalendar download is parsed correctly.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_parse() {
+        let ics = include_str!("official_calendar/tests/official.ics");
+        let official = parse(ics, date(7, 31)).unwrap();
+        assert_eq!(
+            official,
+            WasteData {
+                residual_waste: vec![date(6, 16), date(6, 30), date(7, 14), date(7, 28)],
+                organic_waste: vec![
+                    date(6, 7),
+                    date(6, 14),
+                    date(6, 21),
+                    date(6, 28),
+                    date(7, 5),
+                    date(7, 12),
+                    date(7, 19),
+                    date(7, 26),
+                ],
+                recyclable_waste: vec![date(6, 8), date(6, 22), date(7, 6), date(7, 20)],
+                paper_waste: vec![date(6, 14), date(7, 12)],
+                bulky_waste: vec![date(7, 12)],
+                ..WasteData::default()
+            }
+        );
+        let official = parse(ics, date(12, 31)).unwrap();
+        assert_eq!(
+            official.paper_waste,
+            vec![
+                date(6, 14),
+                date(7, 12),
+                date(8, 9),
+                date(9, 5),
+                date(10, 4),
+                date(11, 1),
+            ]
+        );
+        assert_eq!(official.residual_waste.last(), Some(&date(12, 29)));
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let ics = include_str!("official_calendar/tests/official.ics");
+        let official = parse(ics, date(12, 31)).unwrap();
+        assert_eq!(
+            reconcile(&get_scraped_waste_data(), &official),
+            vec![
+                Disagreement::OnlyScraped {
+                    waste_type: WasteType::Residual,
+                    date: date(6, 29),
+                },
+                Disagreement::OnlyOfficial {
+                    waste_type: WasteType::Residual,
+                    date: date(6, 30),
+                },
+                Disagreement::OnlyScraped {
+                    waste_type: WasteType::Recyclable,
+                    date: date(6, 7),
+                },
+                Disagreement::OnlyOfficial {
+                    waste_type: WasteType::Recyclable,
+                    date: date(6, 8),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let ics = include_str!("official_calendar/tests/official.ics");
+        let official = parse(ics, date(8, 31)).unwrap();
+        let merged = merge(
+            get_scraped_waste_data(),
+            official.clone(),
+            MergeStrategy::PreferScraped,
+        );
+        assert_eq!(
+            merged.residual_waste,
+            vec![
+                date(6, 16),
+                date(6, 29),
+                date(7, 14),
+                date(7, 28),
+                date(8, 11),
+                date(8, 25),
+            ]
+        );
+        assert_eq!(
+            merged.paper_waste,
+            vec![date(6, 14), date(7, 12), date(8, 9)]
+        );
+
+        let merged = merge(
+            get_scraped_waste_data(),
+            official.clone(),
+            MergeStrategy::Scraped,
+        );
+        assert_eq!(merged, get_scraped_waste_data());
+
+        let merged = merge(
+            get_scraped_waste_data(),
+            official.clone(),
+            MergeStrategy::Official,
+        );
+        assert_eq!(merged, official);
+    }
+}
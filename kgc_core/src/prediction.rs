@@ -0,0 +1,233 @@
+//! This prediction engine projects collection dates past the horizon of the official website.
+//!
+//! The dates are projected with the rhythm of each waste type.
+//! Projected dates which fall on a public holiday are brought forward to the previous working day,
+//! which is the usual shift rule in Karlsruhe.
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+use crate::{
+    garbage_client::{WasteData, WasteType},
+    holiday::holiday,
+};
+
+/// The collection rhythm of a waste type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rhythm {
+    pub weekday: Weekday,
+    pub interval_weeks: u32,
+}
+
+impl Rhythm {
+    /// Parse the rhythm header of the official website, e.g. "Freitag, gerade Kalenderwoche" or "Mittwoch, 4-wöchentlich".
+    pub fn parse(header: &str) -> Option<Self> {
+        let header = header.to_lowercase();
+        let weekday = [
+            ("montag", Weekday::Mon),
+            ("dienstag", Weekday::Tue),
+            ("mittwoch", Weekday::Wed),
+            ("donnerstag", Weekday::Thu),
+            ("freitag", Weekday::Fri),
+            ("samstag", Weekday::Sat),
+        ]
+        .into_iter()
+        .find(|(name, _)| header.starts_with(name))
+        .map(|(_, weekday)| weekday)?;
+        let interval_weeks = if header.contains("kalenderwoche") || header.contains("14-täglich") {
+            2
+        } else if header.contains("4-wöchentlich") {
+            4
+        } else {
+            1
+        };
+        Some(Rhythm {
+            weekday,
+            interval_weeks,
+        })
+    }
+
+    /// Derive the rhythm from the spacing of collection dates.
+    ///
+    /// The interval is the shortest spacing rounded to weeks and the weekday is the most frequent one.
+    pub fn from_dates(dates: &[NaiveDate]) -> Option<Self> {
+        let mut dates = dates.to_vec();
+        dates.sort();
+        let shortest_spacing = dates
+            .windows(2)
+            .map(|window| (window[1] - window[0]).num_days())
+            .min()?;
+        let mut weekday_counts: HashMap<Weekday, usize> = HashMap::new();
+        for date in &dates {
+            *weekday_counts.entry(date.weekday()).or_default() += 1;
+        }
+        let weekday = dates
+            .iter()
+            .rev()
+            .map(|date| date.weekday())
+            .max_by_key(|weekday| weekday_counts[weekday])?;
+        Some(Rhythm {
+            weekday,
+            interval_weeks: ((shortest_spacing + 3) / 7).max(1) as u32,
+        })
+    }
+
+    /// Get the regular date of this rhythm which is nearest to a date, i.e. at most three days before or after it.
+    ///
+    /// A collection can be shifted into another calendar week, e.g. from Pfingstmontag to the Saturday before.
+    pub fn regular_date(&self, date: NaiveDate) -> NaiveDate {
+        let days_after = (self.weekday.num_days_from_monday() as i64
+            - date.weekday().num_days_from_monday() as i64)
+            .rem_euclid(7);
+        match days_after {
+            0..=3 => date + Duration::days(days_after),
+            _ => date - Duration::days(7 - days_after),
+        }
+    }
+}
+
+/// Predict the collection dates after the last published date until a specific date.
+///
+/// The predictions are stored in the waste data, the bulky waste collection is not predicted because it is irregular.
+pub fn predict(waste_data: &mut WasteData, until: NaiveDate) {
+    for waste_type in WasteType::ALL {
+        let (Some(last), Some(rhythm)) = (
+            waste_data.dates(waste_type).iter().max().copied(),
+            waste_data.rhythm(waste_type),
+        ) else {
+            continue;
+        };
+        let mut predictions = vec![];
+        let mut regular_date = rhythm.regular_date(last);
+        loop {
+            regular_date += Duration::weeks(rhythm.interval_weeks.into());
+            let date = shift(regular_date);
+            if date > until {
+                break;
+            }
+            if date > last {
+                predictions.push(date);
+            }
+        }
+        waste_data.predictions.insert(waste_type, predictions);
+    }
+}
+
+/// Shift a date which falls on a public holiday to the previous working day.
+fn shift(date: NaiveDate) -> NaiveDate {
+    let mut date = date;
+    while holiday(date).is_some() || date.weekday() == Weekday::Sun {
+        date -= Duration::days(1);
+    }
+    date
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Weekday;
+
+    use crate::{
+        garbage_client::{tests::date, WasteData, WasteType},
+        prediction::{predict, shift, Rhythm},
+    };
+
+    #[test]
+    fn test_parse_rhythm() {
+        assert_eq!(
+            Rhythm::parse("Freitag, gerade Kalenderwoche"),
+            Some(Rhythm {
+                weekday: Weekday::Fri,
+                interval_weeks: 2,
+            })
+        );
+        assert_eq!(
+            Rhythm::parse("Mittwoch, 4-wöchentlich"),
+            Some(Rhythm {
+                weekday: Weekday::Wed,
+                interval_weeks: 4,
+            })
+        );
+        assert_eq!(
+            Rhythm::parse("Mittwoch"),
+            Some(Rhythm {
+                weekday: Weekday::Wed,
+                interval_weeks: 1,
+            })
+        );
+        assert_eq!(Rhythm::parse("Abholservice"), None);
+    }
+
+    #[test]
+    fn test_rhythm_from_dates() {
+        assert_eq!(
+            Rhythm::from_dates(&[date(6, 16), date(6, 29), date(7, 14)]),
+            Some(Rhythm {
+                weekday: Weekday::Fri,
+                interval_weeks: 2,
+            })
+        );
+        assert_eq!(Rhythm::from_dates(&[date(6, 16)]), None);
+    }
+
+    #[test]
+    fn test_regular_date() {
+        let rhythm = Rhythm {
+            weekday: Weekday::Thu,
+            interval_weeks: 1,
+        };
+        // Fronleichnam is brought forward to Wednesday and a collection may be caught up on Saturday.
+        assert_eq!(rhythm.regular_date(date(6, 7)), date(6, 8));
+        assert_eq!(rhythm.regular_date(date(6, 8)), date(6, 8));
+        assert_eq!(rhythm.regular_date(date(6, 10)), date(6, 8));
+        let rhythm = Rhythm {
+            weekday: Weekday::Mon,
+            interval_weeks: 2,
+        };
+        // Pfingstmontag is brought forward to the Saturday or the Friday of the previous week.
+        assert_eq!(rhythm.regular_date(shift(date(5, 29))), date(5, 29));
+        assert_eq!(rhythm.regular_date(date(5, 26)), date(5, 29));
+    }
+
+    #[test]
+    fn test_shift() {
+        assert_eq!(shift(date(6, 8)), date(6, 7));
+        assert_eq!(shift(date(6, 9)), date(6, 9));
+        // Pfingstmontag is brought forward to Saturday.
+        assert_eq!(shift(date(5, 29)), date(5, 27));
+    }
+
+    #[test]
+    fn test_predict() {
+        let mut waste_data = WasteData {
+            organic_waste: vec![date(5, 3), date(5, 10)],
+            recyclable_waste: vec![date(5, 4)],
+            bulky_waste: vec![date(5, 12)],
+            ..WasteData::default()
+        };
+        waste_data.rhythms.insert(
+            WasteType::Recyclable,
+            Rhythm {
+                weekday: Weekday::Thu,
+                interval_weeks: 1,
+            },
+        );
+        predict(&mut waste_data, date(6, 10));
+        assert_eq!(
+            waste_data.predictions[&WasteType::Organic],
+            vec![date(5, 17), date(5, 24), date(5, 31), date(6, 7),]
+        );
+        // Christi Himmelfahrt (May 18th) and Fronleichnam (June 8th) are brought forward.
+        assert_eq!(
+            waste_data.predictions[&WasteType::Recyclable],
+            vec![
+                date(5, 11),
+                date(5, 17),
+                date(5, 25),
+                date(6, 1),
+                date(6, 7)
+            ]
+        );
+        assert!(!waste_data.predictions.contains_key(&WasteType::Bulky));
+    }
+}
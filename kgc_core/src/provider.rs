@@ -0,0 +1,96 @@
+//! This module provides the municipalities whose garbage collection dates can be read.
+//!
+//! Every municipality is implemented as a provider in its own module with its own fixture tests.
+
+pub mod karlsruhe;
+
+use std::{fmt, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{
+    address::Address,
+    garbage_client::{Notice, WasteData, WasteType},
+    provider::karlsruhe::KarlsruheProvider,
+};
+
+/// The identifier of the provider which is used if none is selected.
+pub static DEFAULT_PROVIDER: &str = "karlsruhe";
+
+/// A municipality whose garbage collection dates can be read from its official website.
+#[async_trait]
+pub trait Provider: fmt::Debug + Send + Sync {
+    /// Get the identifier which selects this provider, e.g. in the CLI or in the server path.
+    fn id(&self) -> &'static str;
+
+    /// Get the name of the municipality.
+    fn municipality(&self) -> &'static str;
+
+    /// Get the domain of the municipality, which is part of the UIDs of the events.
+    ///
+    /// Changing the domain of an existing provider is a breaking change!
+    fn domain(&self) -> &'static str;
+
+    /// Get the URL of the official website.
+    fn url(&self) -> &str;
+
+    /// Get the label of a waste type which is used by the official website.
+    fn label(&self, waste_type: WasteType) -> &'static str {
+        waste_type.label()
+    }
+
+    /// Get the official street list.
+    async fn get_streets(&self) -> Result<Vec<String>>;
+
+    /// Get the response of the official server for a resolved address.
+    async fn get_response(&self, address: &Address) -> Result<String>;
+
+    /// Parse a response of the official server for a specific address, e.g. one which has been saved.
+    fn parse_response(&self, address: &Address, response: &str) -> Result<WasteData>;
+
+    /// Get the notices which are currently shown on the official website.
+    async fn get_notices(&self) -> Result<Vec<Notice>> {
+        Ok(vec![])
+    }
+
+    /// Fetch the waste data for a specific address.
+    ///
+    /// By default, the waste data is read from the official website, see [`fetch_website`].
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+        fetch_website(self, address).await
+    }
+}
+
+/// Fetch the waste data for a specific address from the official website of a provider.
+///
+/// The address is resolved against the official street list before the official server is asked for the dates.
+/// The resolved address is returned alongside the waste data.
+/// Providers which override [`Provider::fetch`] call this to read the official website.
+pub async fn fetch_website<P: Provider + ?Sized>(
+    provider: &P,
+    address: &Address,
+) -> Result<(Address, WasteData)> {
+    let address = address.resolve(&provider.get_streets().await?)?;
+    let response = provider.get_response(&address).await?;
+    let waste_data = provider.parse_response(&address, &response)?;
+    Ok((address, waste_data))
+}
+
+/// Get all providers.
+///
+/// The upstream URL replaces the official website of the default provider, e.g. with a mirror.
+pub fn providers(upstream_url: Option<&str>) -> Vec<Arc<dyn Provider>> {
+    let karlsruhe = match upstream_url {
+        Some(url) => KarlsruheProvider::new(url),
+        None => KarlsruheProvider::default(),
+    };
+    vec![Arc::new(karlsruhe)]
+}
+
+/// Get the provider with a specific identifier.
+pub fn provider(id: &str, upstream_url: Option<&str>) -> Option<Arc<dyn Provider>> {
+    providers(upstream_url)
+        .into_iter()
+        .find(|provider| provider.id() == id.to_lowercase())
+}
@@ -0,0 +1,662 @@
+//! This provider reads the garbage collection dates of the city of Karlsruhe from its official website.
+//!
+//! The districts whose waste is collected by a private contractor are read from the schedules of their district offices, see [`district`].
+//! The stops of the mobile hazardous waste collection are read from their own page, see [`hazardous`].
+
+pub mod district;
+pub mod hazardous;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use regex::{Captures, Regex};
+use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+
+use crate::{
+    address::Address,
+    garbage_client::{AddressMismatchError, Notice, Stop, WasteData, WasteType},
+    prediction::Rhythm,
+    provider::{
+        fetch_website,
+        karlsruhe::district::{District, DistrictSchedule},
+        Provider,
+    },
+};
+
+static URL: &str = "https://web6.karlsruhe.de/service/abfall/akal/akal.php";
+/// The time for which the schedules of the districts and of the hazardous waste collection are kept, as they change rarely.
+const CACHE_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// A value which is kept for a while, shared by the clones of the provider.
+#[derive(Debug)]
+struct Cache<T> {
+    ttl: Duration,
+    value: Mutex<Option<(Instant, T)>>,
+}
+
+impl<T: Clone> Cache<T> {
+    fn new(ttl: Duration) -> Self {
+        Cache {
+            ttl,
+            value: Mutex::new(None),
+        }
+    }
+
+    /// Get the value if it has not expired yet.
+    fn get(&self) -> Option<T> {
+        let value = self.value.lock().unwrap();
+        value
+            .as_ref()
+            .filter(|(stored, _)| stored.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    fn set(&self, value: T) {
+        *self.value.lock().unwrap() = Some((Instant::now(), value));
+    }
+}
+
+/// The provider of the city of Karlsruhe.
+#[derive(Debug, Clone)]
+pub struct KarlsruheProvider {
+    url: String,
+    /// The URLs or file paths of the schedules of the districts, the websites of the district offices by default.
+    district_schedules: BTreeMap<District, String>,
+    /// The URL or file path of the schedule of the mobile hazardous waste collection.
+    hazardous_schedule: String,
+    /// The schedules of the districts which have been read last.
+    cached_district_schedules: Arc<Cache<Vec<DistrictSchedule>>>,
+    /// The stops of the mobile hazardous waste collection which have been read last.
+    cached_stops: Arc<Cache<Vec<Stop>>>,
+}
+
+impl KarlsruheProvider {
+    /// Create the provider with another URL of the official website, e.g. a mirror.
+    pub fn new(url: impl Into<String>) -> Self {
+        KarlsruheProvider {
+            url: url.into(),
+            district_schedules: District::ALL
+                .into_iter()
+                .map(|district| (district, String::from(district.office_url())))
+                .collect(),
+            hazardous_schedule: String::from(hazardous::URL),
+            cached_district_schedules: Arc::new(Cache::new(CACHE_TTL)),
+            cached_stops: Arc::new(Cache::new(CACHE_TTL)),
+        }
+    }
+
+    /// Keep the schedules of the districts and of the hazardous waste collection for another time.
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cached_district_schedules = Arc::new(Cache::new(ttl));
+        self.cached_stops = Arc::new(Cache::new(ttl));
+        self
+    }
+
+    /// Read the schedule of a district from another URL or file path.
+    pub fn with_district_schedule(
+        mut self,
+        district: District,
+        location: impl Into<String>,
+    ) -> Self {
+        self.district_schedules.insert(district, location.into());
+        self
+    }
+
+    /// Read the schedule of the mobile hazardous waste collection from another URL or file path.
+    pub fn with_hazardous_schedule(mut self, location: impl Into<String>) -> Self {
+        self.hazardous_schedule = location.into();
+        self
+    }
+
+    /// Get the schedules of all districts, skipping the ones which can not be read.
+    ///
+    /// The schedules are cached, so the pages and PDF files of the district offices are not downloaded for every address.
+    async fn get_district_schedules(&self) -> Vec<DistrictSchedule> {
+        if let Some(schedules) = self.cached_district_schedules.get() {
+            return schedules;
+        }
+        let mut schedules = vec![];
+        for (district, location) in &self.district_schedules {
+            if let Ok(schedule) = district::get(*district, location).await {
+                schedules.push(schedule);
+            }
+        }
+        self.cached_district_schedules.set(schedules.clone());
+        schedules
+    }
+
+    /// Get the stops of the mobile hazardous waste collection.
+    ///
+    /// The stops are cached, so their page is not downloaded for every address, while errors are not cached.
+    async fn get_stops(&self) -> Result<Vec<Stop>> {
+        if let Some(stops) = self.cached_stops.get() {
+            return Ok(stops);
+        }
+        let stops = hazardous::get(&self.hazardous_schedule).await?;
+        self.cached_stops.set(stops.clone());
+        Ok(stops)
+    }
+
+    /// Add the stops of the mobile hazardous waste collection in the district or at the street of an address.
+    ///
+    /// If they cannot be read, a warning is added instead, so the other dates are still served.
+    async fn add_stops(&self, address: &Address, waste_data: &mut WasteData) {
+        match self.get_stops().await {
+            Ok(stops) => hazardous::apply(waste_data, hazardous::matching(&stops, address)),
+            Err(error) => waste_data.warnings.push(format!(
+                "the stops of the mobile hazardous waste collection cannot be read: {error:#}"
+            )),
+        }
+    }
+}
+
+impl Default for KarlsruheProvider {
+    fn default() -> Self {
+        KarlsruheProvider::new(URL)
+    }
+}
+
+#[async_trait]
+impl Provider for KarlsruheProvider {
+    fn id(&self) -> &'static str {
+        "karlsruhe"
+    }
+
+    fn municipality(&self) -> &'static str {
+        "Karlsruhe"
+    }
+
+    fn domain(&self) -> &'static str {
+        "karlsruhe.de"
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    async fn get_streets(&self) -> Result<Vec<String>> {
+        let html = reqwest::get(&self.url).await?.text().await?;
+        Ok(parse_streets(&html))
+    }
+
+    async fn get_response(&self, address: &Address) -> Result<String> {
+        let client = reqwest::Client::new();
+        let response = client
+            .post(&self.url)
+            .form(&HashMap::from([
+                ("strasse_n", address.street.clone()),
+                ("hausnr", address.house_number.upstream()),
+            ]))
+            .send()
+            .await?;
+        Ok(response.text().await?)
+    }
+
+    /// Parse an HTML response of the official server.
+    ///
+    /// The address which the official server resolved is verified before the waste data is parsed.
+    fn parse_response(&self, address: &Address, response: &str) -> Result<WasteData> {
+        verify_address(address, parse_resolved_address(response, &self.url))?;
+        parse(self, response)
+    }
+
+    /// Fetch the waste data for a specific address.
+    ///
+    /// If the official website has no dates for the address or does not know it, the schedules of the districts are searched for its street.
+    /// Other errors, e.g. if the official website can not be reached, are returned without searching the districts.
+    /// The stops of the mobile hazardous waste collection in the district or at the street of the address are added,
+    /// the calendar is still served without them if their schedule can not be read, which is added to the warnings of the waste data.
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+        let result = fetch_website(self, address).await;
+        let is_unknown = match &result {
+            Ok((_, waste_data)) => waste_data.is_empty(),
+            Err(err) => err
+                .downcast_ref::<AddressMismatchError>()
+                .is_some_and(|err| err.resolved_address.is_none()),
+        };
+        let (address, mut waste_data) = match is_unknown {
+            true => match self
+                .get_district_schedules()
+                .await
+                .iter()
+                .find_map(|schedule| schedule.waste_data(address))
+            {
+                Some(found) => found,
+                None => result?,
+            },
+            false => result?,
+        };
+        self.add_stops(&address, &mut waste_data).await;
+        Ok((address, waste_data))
+    }
+
+    async fn get_notices(&self) -> Result<Vec<Notice>> {
+        let html = reqwest::get(&self.url).await?.text().await?;
+        let notices = parse_notices(&Html::parse_document(&html));
+        Ok(notices)
+    }
+}
+
+/// Parse the garbage HTML to usable waste data.
+///
+/// The rows are matched by the labels of the waste types of the provider.
+fn parse(provider: &dyn Provider, html: &str) -> Result<WasteData> {
+    let dom = Html::parse_document(html);
+    let row_selector = Selector::parse(".row").unwrap();
+    let rows = dom.select(&row_selector);
+    let mut residual_waste_dates: Vec<NaiveDate> = vec![];
+    let mut organic_waste_dates: Vec<NaiveDate> = vec![];
+    let mut recyclable_waste_dates: Vec<NaiveDate> = vec![];
+    let mut paper_waste_dates: Vec<NaiveDate> = vec![];
+    let mut bulky_waste_dates: Vec<NaiveDate> = vec![];
+    let mut rhythms: BTreeMap<WasteType, Rhythm> = BTreeMap::new();
+    let mut notes: BTreeMap<(WasteType, NaiveDate), String> = BTreeMap::new();
+    let type_col_selector = Selector::parse(".col_3-2").unwrap();
+    let date_col_selector = Selector::parse(".col_3-3").unwrap();
+    let bulky_waste_date_col_selector = Selector::parse(".col_4-3").unwrap();
+    let rhythm_selector = Selector::parse("b").unwrap();
+    let date_regex = Regex::new(
+        r"(?x)
+            >\s* # the ending of the previous tag
+            \w{2}\.\s # the day of the week in short notation with a dot and a space
+            den\s
+            (?P<day>\d{2}) # the day
+            \.
+            (?P<month>\d{2}) # the month
+            \.
+            (?P<year>\d{4}) # the year
+            (?:\s*\((?P<note>[^)]*)\))? # an optional note, e.g. about a holiday
+        ",
+    )
+    .unwrap();
+    let bulky_waste_date_regex =
+        Regex::new(r">\s*(?P<day>\d{2})\.(?P<month>\d{2})\.(?P<year>\d{4})").unwrap();
+    let date_from_captures = |captures: Captures| -> Option<NaiveDate> {
+        let day: u32 = captures["day"].parse().unwrap();
+        let month: u32 = captures["month"].parse().unwrap();
+        let year: i32 = captures["year"].parse().unwrap();
+        NaiveDate::from_ymd_opt(year, month, day)
+    };
+    let find_dates = |inner_html: &str,
+                      waste_type: WasteType,
+                      notes: &mut BTreeMap<(WasteType, NaiveDate), String>|
+     -> Vec<NaiveDate> {
+        date_regex
+            .captures_iter(inner_html)
+            .filter_map(|captures| {
+                let note = captures
+                    .name("note")
+                    .map(|note| text_of(Html::parse_fragment(note.as_str()).root_element()));
+                let date = date_from_captures(captures)?;
+                if let Some(note) = note {
+                    notes.insert((waste_type, date), note);
+                }
+                Some(date)
+            })
+            .collect()
+    };
+    for row_element in rows {
+        let Some(type_col) = row_element.select(&type_col_selector).next() else {
+            continue;
+        };
+        let type_col_inner_html = type_col.inner_html();
+        let date_col_option = row_element.select(&date_col_selector).next();
+        let date_col_inner_html_option = date_col_option.map(|date_col| date_col.inner_html());
+        let rhythm = date_col_option
+            .and_then(|date_col| date_col.select(&rhythm_selector).next())
+            .and_then(|rhythm_header| Rhythm::parse(&text_of(rhythm_header)));
+        let bulky_waste_date_col_inner_html_option = row_element
+            .select(&bulky_waste_date_col_selector)
+            .next()
+            .map(|date_col| date_col.inner_html());
+        match (
+            date_col_inner_html_option,
+            bulky_waste_date_col_inner_html_option,
+        ) {
+            (Some(date_col_inner_html), _)
+                if type_col_inner_html.contains(provider.label(WasteType::Residual)) =>
+            {
+                residual_waste_dates =
+                    find_dates(&date_col_inner_html, WasteType::Residual, &mut notes);
+                rhythms.extend(rhythm.map(|rhythm| (WasteType::Residual, rhythm)));
+            }
+            (Some(date_col_inner_html), _)
+                if type_col_inner_html.contains(provider.label(WasteType::Organic)) =>
+            {
+                organic_waste_dates =
+                    find_dates(&date_col_inner_html, WasteType::Organic, &mut notes);
+                rhythms.extend(rhythm.map(|rhythm| (WasteType::Organic, rhythm)));
+            }
+            (Some(date_col_inner_html), _)
+                if type_col_inner_html.contains(provider.label(WasteType::Recyclable)) =>
+            {
+                recyclable_waste_dates =
+                    find_dates(&date_col_inner_html, WasteType::Recyclable, &mut notes);
+                rhythms.extend(rhythm.map(|rhythm| (WasteType::Recyclable, rhythm)));
+            }
+            (Some(date_col_inner_html), _)
+                if type_col_inner_html.contains(provider.label(WasteType::Paper)) =>
+            {
+                paper_waste_dates = find_dates(&date_col_inner_html, WasteType::Paper, &mut notes);
+                rhythms.extend(rhythm.map(|rhythm| (WasteType::Paper, rhythm)));
+            }
+            (_, Some(bulky_waste_date_col_inner_html))
+                if type_col_inner_html.contains(provider.label(WasteType::Bulky)) =>
+            {
+                bulky_waste_dates = bulky_waste_date_regex
+                    .captures(&bulky_waste_date_col_inner_html)
+                    .and_then(date_from_captures)
+                    .into_iter()
+                    .collect();
+            }
+            _ => continue,
+        }
+    }
+    let waste_data = WasteData {
+        residual_waste: residual_waste_dates,
+        organic_waste: organic_waste_dates,
+        recyclable_waste: recyclable_waste_dates,
+        paper_waste: paper_waste_dates,
+        bulky_waste: bulky_waste_dates,
+        hazardous_waste: vec![],
+        notices: parse_notices(&dom),
+        rhythms,
+        notes,
+        predictions: BTreeMap::new(),
+        stops: vec![],
+        warnings: vec![],
+    };
+    Ok(waste_data)
+}
+
+/// Parse the active notices which are shown above the form.
+///
+/// Inactive notices are commented out and therefore not part of the DOM.
+fn parse_notices(dom: &Html) -> Vec<Notice> {
+    let paragraph_selector = Selector::parse("#nfoo > .no_print > p").unwrap();
+    let link_selector = Selector::parse("a[href]").unwrap();
+    dom.select(&paragraph_selector)
+        .map(|paragraph| Notice {
+            text: text_of(paragraph),
+            links: paragraph
+                .select(&link_selector)
+                .filter_map(|link| link.value().attr("href"))
+                .map(String::from)
+                .collect(),
+        })
+        .filter(|notice| !notice.text.is_empty() || !notice.links.is_empty())
+        .collect()
+}
+
+/// Get the text of an element with collapsed whitespace.
+fn text_of(element: ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Parse the official street list which is embedded for the autocompletion of the form.
+fn parse_streets(html: &str) -> Vec<String> {
+    let street_list_regex = Regex::new(r"var strassenliste = \[(?P<streets>.*?)\];").unwrap();
+    let street_regex = Regex::new(r"'(?P<street>(?:[^'\\]|\\.)*)'").unwrap();
+    let Some(captures) = street_list_regex.captures(html) else {
+        return vec![];
+    };
+    street_regex
+        .captures_iter(&captures["streets"])
+        .map(|captures| captures["street"].replace("\\'", "'"))
+        .collect()
+}
+
+/// Parse the address which the official server actually resolved from the header row.
+///
+/// The header row links back to the official website with the resolved street and street number in the query string.
+fn parse_resolved_address(html: &str, url: &str) -> Option<(String, String)> {
+    let dom = Html::parse_document(html);
+    let link_selector = Selector::parse(".column-1 a[href]").unwrap();
+    let base = Url::parse(url).ok()?;
+    dom.select(&link_selector).find_map(|link| {
+        let url = base.join(link.value().attr("href")?).ok()?;
+        let query: HashMap<_, _> = url.query_pairs().collect();
+        Some((
+            query.get("strasse")?.to_string(),
+            query.get("hausnr")?.to_string(),
+        ))
+    })
+}
+
+/// Verify that the official server resolved the requested address and did not fall back to another one.
+fn verify_address(
+    address: &Address,
+    resolved_address: Option<(String, String)>,
+) -> Result<(), AddressMismatchError> {
+    match resolved_address {
+        Some((resolved_street, resolved_street_number))
+            if address.matches(&resolved_street, &resolved_street_number) =>
+        {
+            Ok(())
+        }
+        resolved_address => Err(AddressMismatchError {
+            street: address.street.clone(),
+            street_number: address.house_number.to_string(),
+            resolved_address,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs, time::Duration};
+
+    use scraper::Html;
+
+    use crate::{
+        address::Address,
+        garbage_client::{tests::get_test_waste_data, AddressMismatchError, Notice, WasteData},
+        provider::karlsruhe::{
+            district::District, parse, parse_notices, parse_resolved_address, parse_streets,
+            verify_address, KarlsruheProvider, URL,
+        },
+    };
+
+    /// Test whether the HTML is parsed correctly.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_parse() {
+        let html = include_str!("karlsruhe/tests/response.html");
+        let parsed = parse(&KarlsruheProvider::default(), html).unwrap();
+        let expected = get_test_waste_data();
+        assert_eq!(parsed, expected)
+    }
+
+    /// Test whether only the active notices are parsed.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_parse_notices() {
+        let html = include_str!("karlsruhe/tests/response.html");
+        let notices = parse_notices(&Html::parse_document(html));
+        assert!(notices.is_empty());
+
+        let html = html.replacen(
+            "<!--<p><strong>Bitte beachten Sie die Informationen zu den Leerungsänderungen wegen eines Feiertages:</strong></p>\n            <p><a href=\"https://www.karlsruhe.de/b4/buergerdienste/abfall/abfuhraenderung\"> Zu den Einzelheiten</a>\n            <p></p>-->",
+            "<p><strong>Bitte beachten Sie die Informationen zu den Leerungsänderungen wegen eines Feiertages:</strong></p>\n            <p><a href=\"https://www.karlsruhe.de/b4/buergerdienste/abfall/abfuhraenderung\"> Zu den Einzelheiten</a>\n            <p></p>",
+            1,
+        );
+        let notices = parse_notices(&Html::parse_document(&html));
+        assert_eq!(
+            notices,
+            vec![
+                Notice {
+                    text: String::from("Bitte beachten Sie die Informationen zu den Leerungsänderungen wegen eines Feiertages:"),
+                    links: vec![],
+                },
+                Notice {
+                    text: String::from("Zu den Einzelheiten"),
+                    links: vec![String::from(
+                        "https://www.karlsruhe.de/b4/buergerdienste/abfall/abfuhraenderung"
+                    )],
+                },
+            ]
+        );
+    }
+
+    /// Test whether the resolved address is parsed from the header row.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_parse_resolved_address() {
+        let html = include_str!("karlsruhe/tests/response.html");
+        let resolved_address = parse_resolved_address(html, URL);
+        assert_eq!(
+            resolved_address,
+            Some((String::from("<REDACTED>"), String::from("<REDACTED>")))
+        );
+
+        let html = "<div class='row'><div class='column-1'><b><a href='/service/abfall/akal/akal.php?strasse=Schlo%C3%9Fplatz&hausnr=1'>Schloßplatz 1</a></b></div></div>";
+        let resolved_address = parse_resolved_address(html, URL);
+        assert_eq!(
+            resolved_address,
+            Some((String::from("Schloßplatz"), String::from("1")))
+        );
+
+        let resolved_address = parse_resolved_address("<div class='row'></div>", URL);
+        assert_eq!(resolved_address, None);
+    }
+
+    #[test]
+    fn test_verify_address() {
+        let resolved_address = Some((String::from("Schloßplatz"), String::from("1")));
+        let address = Address::new("Schloßplatz", "1").unwrap();
+        assert!(verify_address(&address, resolved_address.clone()).is_ok());
+        let address = Address::new(" schlossplatz ", "1").unwrap();
+        assert!(verify_address(&address, resolved_address.clone()).is_ok());
+        let address = Address::new("Schlossplatz", "2").unwrap();
+        assert_eq!(
+            verify_address(&address, resolved_address.clone()),
+            Err(AddressMismatchError {
+                street: String::from("Schlossplatz"),
+                street_number: String::from("2"),
+                resolved_address,
+            })
+        );
+        let address = Address::new("Schlossplatz", "1").unwrap();
+        assert_eq!(
+            verify_address(&address, None),
+            Err(AddressMismatchError {
+                street: String::from("Schlossplatz"),
+                street_number: String::from("1"),
+                resolved_address: None,
+            })
+        );
+    }
+
+    /// Test whether the official street list is parsed.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_parse_streets() {
+        let html = include_str!("karlsruhe/tests/response.html");
+        let streets = parse_streets(html);
+        assert_eq!(streets.len(), 1860);
+        assert_eq!(streets.first().unwrap(), "Abraham-Lincoln-Allee");
+        assert!(streets.contains(&String::from("Schloßplatz")));
+        assert!(streets.contains(&String::from("Kirchau (Gewann)")));
+    }
+
+    /// Test whether the schedules of the districts are read again only after the time to live of the cache.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_district_schedules_cache() {
+        let directory = temp_dir().join("kgc_test_district_schedules_cache");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let location = directory.join("groetzingen.html");
+        let location = location.to_str().unwrap();
+        for (ttl, expected) in [(Duration::from_secs(60), 1), (Duration::ZERO, 0)] {
+            fs::write(
+                location,
+                include_str!("karlsruhe/district/tests/groetzingen.html"),
+            )
+            .unwrap();
+            let provider = KarlsruheProvider::default()
+                .with_cache_ttl(ttl)
+                .with_district_schedule(District::Groetzingen, location);
+            let provider = KarlsruheProvider {
+                district_schedules: provider
+                    .district_schedules
+                    .into_iter()
+                    .filter(|(district, _)| *district == District::Groetzingen)
+                    .collect(),
+                ..provider
+            };
+            assert_eq!(provider.get_district_schedules().await.len(), 1);
+            fs::remove_file(location).unwrap();
+            assert_eq!(provider.get_district_schedules().await.len(), expected);
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    /// Test whether the stops of the mobile hazardous waste collection are cached, but the errors are not.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_stops_cache() {
+        let directory = temp_dir().join("kgc_test_stops_cache");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let location = directory.join("schadstoffsammlung.html");
+        let provider =
+            KarlsruheProvider::default().with_hazardous_schedule(location.to_str().unwrap());
+        assert!(provider.get_stops().await.is_err());
+        fs::write(
+            &location,
+            include_str!("karlsruhe/hazardous/tests/schadstoffsammlung.html"),
+        )
+        .unwrap();
+        assert_eq!(provider.get_stops().await.unwrap().len(), 5);
+        fs::remove_file(&location).unwrap();
+        assert_eq!(provider.get_stops().await.unwrap().len(), 5);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+    /// Test whether a schedule of the stops which cannot be read is a warning of the waste data.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_add_stops() {
+        let directory = temp_dir().join("kgc_test_add_stops");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let location = directory.join("schadstoffsammlung.html");
+        let provider =
+            KarlsruheProvider::default().with_hazardous_schedule(location.to_str().unwrap());
+        let address = Address::new("Schlossplatz", "1").unwrap();
+        let mut waste_data = WasteData::default();
+        provider.add_stops(&address, &mut waste_data).await;
+        assert!(waste_data.stops.is_empty());
+        assert_eq!(waste_data.warnings.len(), 1);
+        assert!(waste_data.warnings[0]
+            .starts_with("the stops of the mobile hazardous waste collection cannot be read"));
+        fs::write(
+            &location,
+            include_str!("karlsruhe/hazardous/tests/schadstoffsammlung.html"),
+        )
+        .unwrap();
+        let mut waste_data = WasteData::default();
+        provider.add_stops(&address, &mut waste_data).await;
+        assert_eq!(waste_data.stops.len(), 1);
+        assert!(waste_data.warnings.is_empty());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
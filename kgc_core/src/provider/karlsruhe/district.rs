@@ -0,0 +1,357 @@
+//! This module reads the schedules of the districts of Karlsruhe whose waste is collected by a private contractor.
+//!
+//! The district offices publish their schedules as HTML pages or PDF files, which are both reduced to lines of text.
+//! A line starting with "Bezirk" starts a new area of the district, a line starting with "Straßen:" lists the streets of the area
+//! and a line starting with a waste type lists its dates.
+//! Dates without a year get the year of the last heading which names one, e.g. "Abfuhrkalender 2023".
+
+pub mod html;
+pub mod pdf;
+
+use std::{fmt, fs, path::Path};
+
+use anyhow::{bail, Result};
+use chrono::NaiveDate;
+use regex::Regex;
+use reqwest::Url;
+
+use crate::{
+    address::Address,
+    garbage_client::{Notice, WasteData, WasteType},
+};
+
+/// A district of Karlsruhe whose waste is collected by a private contractor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum District {
+    Groetzingen,
+    Hohenwettersbach,
+    Wettersbach,
+    Stupferich,
+}
+
+impl District {
+    pub const ALL: [District; 4] = [
+        District::Groetzingen,
+        District::Hohenwettersbach,
+        District::Wettersbach,
+        District::Stupferich,
+    ];
+
+    /// Get the name of the district.
+    pub fn name(&self) -> &'static str {
+        match self {
+            District::Groetzingen => "Grötzingen",
+            District::Hohenwettersbach => "Hohenwettersbach",
+            District::Wettersbach => "Wettersbach",
+            District::Stupferich => "Stupferich",
+        }
+    }
+
+    /// Get the website of the district office as linked by the official website, which publishes the schedule or links to it.
+    pub fn office_url(&self) -> &'static str {
+        match self {
+            District::Groetzingen => "https://www.karlsruhe.de/stadt-rathaus/stadtteile-ortsverwaltungen/groetzingen/leben-in-groetzingen",
+            District::Hohenwettersbach => "https://www.karlsruhe.de/stadt-rathaus/stadtteile-umgebung/ortsverwaltung-hohenwettersbach/leben-in-hohenwettersbach",
+            District::Wettersbach => "https://www.karlsruhe.de/stadt-rathaus/stadtteile-umgebung/ortsverwaltung-wettersbach/buergerservice",
+            District::Stupferich => "https://www.karlsruhe.de/stadt-rathaus/stadtteile-ortsverwaltungen/stupferich/rathaus-und-buergerservice",
+        }
+    }
+}
+
+impl fmt::Display for District {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The schedule which a district office publishes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistrictSchedule {
+    pub district: District,
+    pub areas: Vec<Area>,
+}
+
+/// An area of a district with its own collection dates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Area {
+    pub name: String,
+    pub streets: Vec<String>,
+    pub waste_data: WasteData,
+}
+
+impl DistrictSchedule {
+    /// Get the waste data of a specific address, if its street is part of this district.
+    ///
+    /// The resolved address is returned alongside the waste data, which notes that the dates come from the district office.
+    pub fn waste_data(&self, address: &Address) -> Option<(Address, WasteData)> {
+        let (area, street) = self.areas.iter().find_map(|area| {
+            let street = area
+                .streets
+                .iter()
+                .find(|street| address.matches_street(street))?;
+            Some((area, street))
+        })?;
+        let mut waste_data = area.waste_data.clone();
+        waste_data.notices.push(Notice {
+            text: format!(
+                "In {} werden die Abfallbehälter von einem privaten Entsorgungsunternehmen geleert, die Termine stammen von der Ortsverwaltung.",
+                self.district
+            ),
+            links: vec![String::from(self.district.office_url())],
+        });
+        let address = Address {
+            street: street.clone(),
+            house_number: address.house_number.clone(),
+            district: Some(String::from(self.district.name())),
+            requested: address.requested.clone(),
+        };
+        Some((address, waste_data))
+    }
+}
+
+/// Get the schedule of a district from a URL or a file path.
+///
+/// An HTML page without dates is followed to the first PDF file it links, because most district offices publish their schedule as one.
+pub async fn get(district: District, location: &str) -> Result<DistrictSchedule> {
+    let content = read(location).await?;
+    if pdf::is_pdf(&content) {
+        return parse(district, pdf::lines(&content)?);
+    }
+    let html = String::from_utf8_lossy(&content);
+    match parse(district, html::lines(&html)) {
+        Ok(schedule) => Ok(schedule),
+        Err(err) => {
+            let Some(link) = html::pdf_link(&html) else {
+                return Err(err);
+            };
+            let content = read(&join(location, &link)?).await?;
+            parse(district, pdf::lines(&content)?)
+        }
+    }
+}
+
+/// Read the content of a URL or a file path.
+async fn read(location: &str) -> Result<Vec<u8>> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        Ok(reqwest::get(location).await?.bytes().await?.to_vec())
+    } else {
+        Ok(fs::read(location)?)
+    }
+}
+
+/// Join a link with the URL or the file path it is linked from.
+fn join(location: &str, link: &str) -> Result<String> {
+    if let Ok(base) = Url::parse(location) {
+        return Ok(base.join(link)?.to_string());
+    }
+    let file_name = link.rsplit('/').next().unwrap_or(link);
+    Ok(Path::new(location)
+        .with_file_name(file_name)
+        .to_string_lossy()
+        .to_string())
+}
+
+/// Parse the lines of text of a schedule.
+pub fn parse(district: District, lines: Vec<String>) -> Result<DistrictSchedule> {
+    let area_regex = Regex::new(r"^(?:Abfuhr)?[Bb]ezirk\b").unwrap();
+    let streets_regex = Regex::new(r"^Straßen:\s*(?P<streets>.*)$").unwrap();
+    let year_regex = Regex::new(r"\b(?P<year>20\d{2})\b").unwrap();
+    let date_regex =
+        Regex::new(r"\b(?P<day>\d{1,2})\.(?P<month>\d{1,2})\.(?P<year>\d{4})?").unwrap();
+    let mut areas: Vec<Area> = vec![];
+    let mut year: Option<i32> = None;
+    let current_area = |areas: &mut Vec<Area>| -> usize {
+        if areas.is_empty() {
+            areas.push(Area {
+                name: String::from(district.name()),
+                ..Area::default()
+            });
+        }
+        areas.len() - 1
+    };
+    for line in lines {
+        if area_regex.is_match(&line) {
+            areas.push(Area {
+                name: line,
+                ..Area::default()
+            });
+            continue;
+        }
+        if let Some(captures) = streets_regex.captures(&line) {
+            let index = current_area(&mut areas);
+            areas[index].streets.extend(
+                captures["streets"]
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|street| !street.is_empty())
+                    .map(String::from),
+            );
+            continue;
+        }
+        let Some(waste_type) = waste_type_of(&line) else {
+            if !date_regex.is_match(&line) {
+                if let Some(captures) = year_regex.captures(&line) {
+                    year = captures["year"].parse().ok();
+                }
+            }
+            continue;
+        };
+        let dates: Vec<NaiveDate> = date_regex
+            .captures_iter(&line)
+            .filter_map(|captures| {
+                let year = match captures.name("year") {
+                    Some(year) => year.as_str().parse().ok()?,
+                    None => year?,
+                };
+                NaiveDate::from_ymd_opt(
+                    year,
+                    captures["month"].parse().ok()?,
+                    captures["day"].parse().ok()?,
+                )
+            })
+            .collect();
+        let index = current_area(&mut areas);
+        areas[index].waste_data.dates_mut(waste_type).extend(dates);
+    }
+    for area in &mut areas {
+        for waste_type in WasteType::ALL {
+            let dates = area.waste_data.dates_mut(waste_type);
+            dates.sort();
+            dates.dedup();
+        }
+    }
+    if areas.iter().all(|area| area.waste_data.is_empty()) {
+        bail!("the schedule of {district} contains no collection dates");
+    }
+    Ok(DistrictSchedule { district, areas })
+}
+
+/// Get the waste type of a line by the label it starts with.
+///
+/// The district offices do not use the labels of the official website, e.g. "Gelbe Tonne" or "Altpapier".
+fn waste_type_of(line: &str) -> Option<WasteType> {
+    let line = line.to_lowercase();
+    [
+        (&["restmüll", "restabfall"][..], WasteType::Residual),
+        (
+            &["bioabfall", "biomüll", "biotonne"][..],
+            WasteType::Organic,
+        ),
+        (
+            &["wertstoff", "gelbe tonne", "gelber sack"][..],
+            WasteType::Recyclable,
+        ),
+        (
+            &["papier", "altpapier", "blaue tonne"][..],
+            WasteType::Paper,
+        ),
+        (&["sperrmüll"][..], WasteType::Bulky),
+    ]
+    .into_iter()
+    .find(|(labels, _)| labels.iter().any(|label| line.starts_with(label)))
+    .map(|(_, waste_type)| waste_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        address::Address,
+        garbage_client::{tests::date, WasteType},
+        provider::karlsruhe::district::{html, join, parse, pdf, waste_type_of, District},
+    };
+
+    /// Test whether an HTML schedule with several areas is parsed.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_parse_html() {
+        let html = include_str!("district/tests/groetzingen.html");
+        let schedule = parse(District::Groetzingen, html::lines(html)).unwrap();
+        assert_eq!(schedule.areas.len(), 2);
+        let area = &schedule.areas[1];
+        assert_eq!(area.name, "Bezirk 2");
+        assert_eq!(
+            area.streets,
+            vec!["Eisenbahnstraße", "Talstraße", "Im Speitel"]
+        );
+        assert_eq!(
+            area.waste_data.residual_waste,
+            vec![date(6, 7), date(6, 21), date(7, 5)]
+        );
+        assert_eq!(
+            area.waste_data.recyclable_waste,
+            vec![date(6, 14), date(6, 28)]
+        );
+        assert_eq!(area.waste_data.bulky_waste, vec![date(9, 16)]);
+    }
+
+    /// Test whether a PDF schedule with dates without a year is parsed.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_parse_pdf() {
+        let content = include_bytes!("district/tests/stupferich.pdf");
+        assert!(pdf::is_pdf(content));
+        let schedule = parse(District::Stupferich, pdf::lines(content).unwrap()).unwrap();
+        assert_eq!(schedule.areas.len(), 1);
+        let area = &schedule.areas[0];
+        assert_eq!(area.name, "Stupferich");
+        assert_eq!(area.streets.len(), 4);
+        assert_eq!(
+            area.waste_data.organic_waste,
+            vec![date(6, 5), date(6, 12), date(6, 19), date(6, 26)]
+        );
+        assert_eq!(area.waste_data.paper_waste, vec![date(6, 14)]);
+    }
+
+    /// Test whether a page without a schedule is rejected and its linked PDF file is found.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_pdf_link() {
+        let html = include_str!("district/tests/wettersbach.html");
+        assert!(parse(District::Wettersbach, html::lines(html)).is_err());
+        let link = html::pdf_link(html).unwrap();
+        assert_eq!(
+            join(District::Wettersbach.office_url(), &link).unwrap(),
+            "https://www.karlsruhe.de/media/ortsverwaltung-wettersbach/abfuhrkalender-2023.pdf"
+        );
+        assert_eq!(
+            join("schedules/wettersbach.html", &link).unwrap(),
+            "schedules/abfuhrkalender-2023.pdf"
+        );
+    }
+
+    /// Test whether the area of an address is detected by its street.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_waste_data() {
+        let html = include_str!("district/tests/groetzingen.html");
+        let schedule = parse(District::Groetzingen, html::lines(html)).unwrap();
+        let address = Address::new("niddastr.", "12a").unwrap();
+        let (address, waste_data) = schedule.waste_data(&address).unwrap();
+        assert_eq!(address.street, "Niddastraße");
+        assert_eq!(
+            waste_data.residual_waste,
+            vec![date(6, 6), date(6, 20), date(7, 4)]
+        );
+        assert_eq!(waste_data.notices.len(), 1);
+        let address = Address::new("Kaiserstraße", "1").unwrap();
+        assert!(schedule.waste_data(&address).is_none());
+    }
+
+    #[test]
+    fn test_waste_type_of() {
+        assert_eq!(
+            waste_type_of("Restmüll 06.06.2023"),
+            Some(WasteType::Residual)
+        );
+        assert_eq!(
+            waste_type_of("Gelbe Tonne 13.06.2023"),
+            Some(WasteType::Recyclable)
+        );
+        assert_eq!(waste_type_of("Altpapier"), Some(WasteType::Paper));
+        assert_eq!(waste_type_of("Abfuhrtermine 2023"), None);
+    }
+}
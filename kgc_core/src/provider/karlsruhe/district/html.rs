@@ -0,0 +1,27 @@
+//! This module reduces the HTML pages of the district offices to lines of text.
+
+use scraper::{Html, Selector};
+
+use crate::provider::karlsruhe::text_of;
+
+/// Get the lines of text of an HTML page.
+///
+/// Every heading, paragraph, list item and table row is a line, the cells of a row are joined by a space.
+pub fn lines(html: &str) -> Vec<String> {
+    let dom = Html::parse_document(html);
+    let line_selector = Selector::parse("h1, h2, h3, h4, h5, h6, p, li, tr").unwrap();
+    dom.select(&line_selector)
+        .map(text_of)
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Get the first link of an HTML page to a PDF file.
+pub fn pdf_link(html: &str) -> Option<String> {
+    let dom = Html::parse_document(html);
+    let link_selector = Selector::parse("a[href]").unwrap();
+    dom.select(&link_selector)
+        .filter_map(|link| link.value().attr("href"))
+        .find(|href| href.to_lowercase().ends_with(".pdf"))
+        .map(String::from)
+}
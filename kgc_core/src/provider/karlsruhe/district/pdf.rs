@@ -0,0 +1,21 @@
+//! This module reduces the PDF files of the district offices to lines of text.
+
+use anyhow::Result;
+
+/// Check whether content is a PDF file by its signature.
+pub fn is_pdf(content: &[u8]) -> bool {
+    content.starts_with(b"%PDF-")
+}
+
+/// Get the lines of text of a PDF file.
+///
+/// The text of a table row is extracted as one line, because its cells share a baseline.
+pub fn lines(content: &[u8]) -> Result<Vec<String>> {
+    let text = pdf_extract::extract_text_from_mem(content)?;
+    let lines = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect();
+    Ok(lines)
+}
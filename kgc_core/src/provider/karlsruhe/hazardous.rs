@@ -0,0 +1,208 @@
+//! This module reads the schedule of the mobile hazardous waste collection ("Mobile Schadstoffsammlung"), which the official website links to.
+//!
+//! The hazardous waste is collected at stops in the districts for a few hours,
+//! so the stops are matched to the district or the street of an address.
+
+use std::fs;
+
+use anyhow::{bail, Result};
+use chrono::{NaiveDate, NaiveTime};
+use regex::Regex;
+use scraper::{Html, Selector};
+
+use crate::{
+    address::Address,
+    garbage_client::{Stop, WasteData},
+    provider::karlsruhe::text_of,
+};
+
+/// The page of the official website which lists the stops.
+pub static URL: &str = "https://www.karlsruhe.de/stadt-rathaus/service-buergerinformation/abfallwirtschaft/entsorgungseinrichtungen";
+
+/// Get the stops from a URL or a file path.
+///
+/// A page without any stops is an error, because the layout of the table has most likely changed.
+pub async fn get(location: &str) -> Result<Vec<Stop>> {
+    let html = if location.starts_with("http://") || location.starts_with("https://") {
+        reqwest::get(location)
+            .await?
+            .error_for_status()?
+            .text()
+            .await?
+    } else {
+        fs::read_to_string(location)?
+    };
+    let stops = parse(&html);
+    if stops.is_empty() {
+        bail!("the schedule {location} lists no stops");
+    }
+    Ok(stops)
+}
+
+/// Parse the stops from the table rows with a date, a time window, a district and a location.
+///
+/// Rows which do not match, e.g. the header row or remarks spanning the whole table, are skipped.
+pub fn parse(html: &str) -> Vec<Stop> {
+    let dom = Html::parse_document(html);
+    let row_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("td").unwrap();
+    let date_regex = Regex::new(r"(?P<day>\d{2})\.(?P<month>\d{2})\.(?P<year>\d{4})").unwrap();
+    let time_regex = Regex::new(
+        r"(?P<start_hour>\d{1,2})[:.](?P<start_minute>\d{2})\s*(?:-|–|bis)\s*(?P<end_hour>\d{1,2})[:.](?P<end_minute>\d{2})",
+    )
+    .unwrap();
+    dom.select(&row_selector)
+        .filter_map(|row| {
+            let cells: Vec<String> = row.select(&cell_selector).map(text_of).collect();
+            let [date, time, district, location] = &cells[..] else {
+                return None;
+            };
+            let date = date_regex.captures(date)?;
+            let time = time_regex.captures(time)?;
+            Some(Stop {
+                date: NaiveDate::from_ymd_opt(
+                    date["year"].parse().ok()?,
+                    date["month"].parse().ok()?,
+                    date["day"].parse().ok()?,
+                )?,
+                start: NaiveTime::from_hms_opt(
+                    time["start_hour"].parse().ok()?,
+                    time["start_minute"].parse().ok()?,
+                    0,
+                )?,
+                end: NaiveTime::from_hms_opt(
+                    time["end_hour"].parse().ok()?,
+                    time["end_minute"].parse().ok()?,
+                    0,
+                )?,
+                district: district.clone(),
+                location: location.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Get the stops in the district of an address or at its street.
+pub fn matching(stops: &[Stop], address: &Address) -> Vec<Stop> {
+    let district = address.district.as_deref().map(normalize_district);
+    stops
+        .iter()
+        .filter(|stop| {
+            district.as_deref() == Some(normalize_district(&stop.district).as_str())
+                || stop
+                    .location
+                    .split(',')
+                    .any(|part| address.matches_street(part))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Add stops to waste data, their dates are the hazardous waste dates.
+pub fn apply(waste_data: &mut WasteData, mut stops: Vec<Stop>) {
+    stops.sort();
+    waste_data.hazardous_waste = stops.iter().map(|stop| stop.date).collect();
+    waste_data.hazardous_waste.dedup();
+    waste_data.stops = stops;
+}
+
+/// Normalize a district, so "Innenstadt-West" matches "innenstadt west".
+fn normalize_district(district: &str) -> String {
+    district
+        .to_lowercase()
+        .chars()
+        .filter(|char| char.is_alphanumeric())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs};
+
+    use chrono::{NaiveDate, NaiveTime};
+
+    use crate::{
+        address::Address,
+        garbage_client::{tests::date, Stop, WasteData},
+        provider::karlsruhe::hazardous::{apply, get, matching, parse},
+    };
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    /// Test whether the stops are parsed.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_parse() {
+        let html = include_str!("hazardous/tests/schadstoffsammlung.html");
+        let stops = parse(html);
+        assert_eq!(stops.len(), 5);
+        assert_eq!(
+            stops[2],
+            Stop {
+                date: date(6, 13),
+                start: time(14, 0),
+                end: time(18, 0),
+                district: String::from("Innenstadt-West"),
+                location: String::from("Schloßplatz"),
+            }
+        );
+    }
+
+    /// Test whether the stops are matched by the district or the street of an address.
+    ///
+    /// This test is offline.
+    #[test]
+    fn test_matching() {
+        let stops = parse(include_str!("hazardous/tests/schadstoffsammlung.html"));
+        let address = Address::new("Pfinztalstraße", "1")
+            .unwrap()
+            .with_district(Some("durlach"));
+        let dates: Vec<NaiveDate> = matching(&stops, &address)
+            .into_iter()
+            .map(|stop| stop.date)
+            .collect();
+        assert_eq!(dates, vec![date(6, 10), date(7, 8)]);
+        let address = Address::new("Schlossplatz", "1").unwrap();
+        let dates: Vec<NaiveDate> = matching(&stops, &address)
+            .into_iter()
+            .map(|stop| stop.date)
+            .collect();
+        assert_eq!(dates, vec![date(6, 13)]);
+        let address = Address::new("Kaiserstraße", "1").unwrap();
+        assert!(matching(&stops, &address).is_empty());
+    }
+
+    #[test]
+    fn test_apply() {
+        let stops = parse(include_str!("hazardous/tests/schadstoffsammlung.html"));
+        let mut waste_data = WasteData::default();
+        apply(&mut waste_data, stops[..2].to_vec());
+        assert_eq!(waste_data.hazardous_waste, vec![date(6, 10)]);
+        assert_eq!(waste_data.stops.len(), 2);
+    }
+
+    /// Test whether a schedule which can not be read or lists no stops is an error.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_get() {
+        let directory = temp_dir().join("kgc_test_hazardous_get");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let location = directory.join("schadstoffsammlung.html");
+        let location = location.to_str().unwrap();
+        assert!(get(location).await.is_err());
+        fs::write(location, "<html><body><p>Umbau</p></body></html>").unwrap();
+        assert!(get(location).await.is_err());
+        fs::write(
+            location,
+            include_str!("hazardous/tests/schadstoffsammlung.html"),
+        )
+        .unwrap();
+        assert_eq!(get(location).await.unwrap().len(), 5);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
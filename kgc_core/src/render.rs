@@ -0,0 +1,594 @@
+//! This module holds the options which filter and mark the events of a rendered calendar.
+//!
+//! The options apply to all kinds of events alike, i.e. published, shifted, predicted dates and stops.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime};
+use regex::Regex;
+
+static FORMAT: &str = "%Y-%m-%d";
+/// The default anchor of a rotation, a Monday.
+static ROTATION_START: (i32, u32, u32) = (1970, 1, 5);
+
+/// The options of a rendered calendar.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Add separate events for collections which deviate from the regular weekday, so nobody puts the bin out on the usual day by habit.
+    pub mark_shifted: bool,
+    /// Omit dates before this date.
+    pub from: Option<NaiveDate>,
+    /// Omit dates after this date.
+    pub to: Option<NaiveDate>,
+    /// Omit dates after this number of days from today.
+    pub horizon_days: Option<i64>,
+    /// Ranges of dates without collection alerts, e.g. a vacation.
+    pub blackouts: Vec<Blackout>,
+    /// Whether dates in a blackout are dropped or marked.
+    pub blackout_mode: BlackoutMode,
+    /// The rotation of the household members who take the bins out.
+    pub rotation: Option<Rotation>,
+    /// The reminders before every collection and stop.
+    pub alarms: Vec<Alarm>,
+    /// The time at which the bins have to be out, which turns the all-day events of the collections into events at that time.
+    pub time_of_day: Option<NaiveTime>,
+}
+
+/// An inclusive range of dates without collection alerts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Blackout {
+    pub from: NaiveDate,
+    pub until: NaiveDate,
+}
+
+/// What happens to dates in a blackout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlackoutMode {
+    /// Omit the dates.
+    #[default]
+    Drop,
+    /// Keep the dates in separate events which are marked as being away.
+    Mark,
+}
+
+/// A round-robin rotation of the household members who take the bins out.
+///
+/// The assignment only depends on the regular date of a collection and, per collection, on the collections before it since the start,
+/// so it does not change when a collection is shifted or when the spacing of the dates changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rotation {
+    pub assignees: Vec<Assignee>,
+    pub mode: RotationMode,
+    /// A date in the week in which the first assignee starts, the rotation is continued before and after it.
+    ///
+    /// Per collection, the first known collection of each waste type is the start by default.
+    pub start: Option<NaiveDate>,
+}
+
+/// A household member, e.g. "Anna" or "Anna <anna@example.org>".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignee {
+    pub name: String,
+    pub email: Option<String>,
+}
+
+/// A reminder before the start of an event, e.g. "6h" before an all-day event is at 18:00 on the day before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alarm {
+    pub before: Duration,
+}
+
+/// How a rotation advances.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RotationMode {
+    /// The next assignee takes the next collection of each waste type.
+    #[default]
+    Collection,
+    /// The next assignee takes all collections of the next week.
+    Week,
+}
+
+impl RenderOptions {
+    /// Get the last date which is rendered, the earlier one of `to` and the horizon.
+    pub fn until(&self, today: NaiveDate) -> Option<NaiveDate> {
+        let horizon = self
+            .horizon_days
+            .map(|horizon_days| today + Duration::days(horizon_days));
+        match (self.to, horizon) {
+            (Some(to), Some(horizon)) => Some(to.min(horizon)),
+            (to, horizon) => to.or(horizon),
+        }
+    }
+
+    /// Check whether a date is rendered, i.e. it is within the date window and not dropped by a blackout.
+    pub fn is_rendered(&self, date: NaiveDate, today: NaiveDate) -> bool {
+        self.from.map_or(true, |from| from <= date)
+            && self.until(today).map_or(true, |until| date <= until)
+            && !(self.blackout_mode == BlackoutMode::Drop && self.blackout(date).is_some())
+    }
+
+    /// Check whether a date is marked because it is in a blackout.
+    pub fn is_marked(&self, date: NaiveDate) -> bool {
+        self.blackout_mode == BlackoutMode::Mark && self.blackout(date).is_some()
+    }
+
+    /// Group the marked dates by the first blackout they are in.
+    pub fn marked(&self, dates: &[NaiveDate]) -> Vec<(&Blackout, Vec<NaiveDate>)> {
+        self.blackouts
+            .iter()
+            .filter_map(|blackout| {
+                let dates: Vec<NaiveDate> = dates
+                    .iter()
+                    .copied()
+                    .filter(|date| self.is_marked(*date) && self.blackout(*date) == Some(blackout))
+                    .collect();
+                (!dates.is_empty()).then_some((blackout, dates))
+            })
+            .collect()
+    }
+
+    /// Get the first blackout a date is in.
+    fn blackout(&self, date: NaiveDate) -> Option<&Blackout> {
+        self.blackouts
+            .iter()
+            .find(|blackout| blackout.contains(date))
+    }
+}
+
+impl Rotation {
+    /// Get the assignee of a collection by its regular date and the regular dates of all known collections of its waste type.
+    ///
+    /// Per collection, the index of the assignee is the number of collections between the start and the regular date.
+    /// The collections before the first known one are counted by the spacing of the first two known ones,
+    /// so the assignments do not change when the official website drops past collections.
+    pub fn assignee(
+        &self,
+        regular_date: NaiveDate,
+        regular_dates: &[NaiveDate],
+    ) -> Option<&Assignee> {
+        if self.assignees.is_empty() {
+            return None;
+        }
+        let monday =
+            |date: NaiveDate| date - Duration::days(date.weekday().num_days_from_monday().into());
+        let (year, month, day) = ROTATION_START;
+        let start = self
+            .start
+            .or_else(|| NaiveDate::from_ymd_opt(year, month, day))?;
+        let index = match self.mode {
+            RotationMode::Collection => {
+                let mut dates = regular_dates.to_vec();
+                dates.push(regular_date);
+                dates.sort();
+                dates.dedup();
+                let start = monday(start);
+                let position =
+                    |date: NaiveDate| dates.partition_point(|other| *other < date) as i64;
+                let unknown = match dates[..] {
+                    [first, second, ..] if start < first => {
+                        (first - start).num_days() / (second - first).num_days()
+                    }
+                    _ => 0,
+                };
+                unknown + position(regular_date) - position(start)
+            }
+            RotationMode::Week => (regular_date - monday(start)).num_days().div_euclid(7),
+        };
+        self.assignees
+            .get(index.rem_euclid(self.assignees.len() as i64) as usize)
+    }
+}
+
+impl Assignee {
+    /// Get the calendar user address of this assignee, "invalid:nomail" is used by common clients for attendees without an email.
+    pub fn cal_address(&self) -> String {
+        match &self.email {
+            Some(email) => format!("mailto:{email}"),
+            None => String::from("invalid:nomail"),
+        }
+    }
+}
+
+impl FromStr for Assignee {
+    type Err = String;
+
+    /// Parse an assignee, e.g. "Anna" or "Anna <anna@example.org>".
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, email) = match value
+            .trim()
+            .strip_suffix('>')
+            .and_then(|value| value.split_once('<'))
+        {
+            Some((name, email)) => (name.trim(), Some(String::from(email.trim()))),
+            None => (value.trim(), None),
+        };
+        if name.is_empty() || name.contains(['"', '<', '>']) {
+            return Err(format!(
+                "the assignee \"{value}\" is not a name like Anna <anna@example.org>"
+            ));
+        }
+        Ok(Assignee {
+            name: String::from(name),
+            email,
+        })
+    }
+}
+
+impl fmt::Display for Assignee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.email {
+            Some(email) => write!(f, "{} <{email}>", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl Alarm {
+    /// Get the value of the `TRIGGER` property, e.g. "-P1DT6H".
+    pub fn trigger(&self) -> String {
+        let minutes = self.before.num_minutes();
+        let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+        let mut trigger = String::from("-P");
+        if days > 0 {
+            trigger.push_str(&format!("{days}D"));
+        }
+        if hours > 0 || minutes > 0 || days == 0 {
+            trigger.push('T');
+        }
+        if hours > 0 {
+            trigger.push_str(&format!("{hours}H"));
+        }
+        if minutes > 0 || (days == 0 && hours == 0) {
+            trigger.push_str(&format!("{minutes}M"));
+        }
+        trigger
+    }
+}
+
+impl FromStr for Alarm {
+    type Err = String;
+
+    /// Parse an alarm, e.g. "1d", "6h" or "1d12h30m".
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let alarm_regex = Regex::new(
+            r"^(?:(?P<days>\d{1,4})d)?(?:(?P<hours>\d{1,4})h)?(?:(?P<minutes>\d{1,4})m)?$",
+        )
+        .unwrap();
+        let invalid = || format!("the alarm \"{value}\" is not a duration like 1d, 6h or 30m");
+        let normalized = value.trim().to_lowercase();
+        let captures = alarm_regex
+            .captures(&normalized)
+            .filter(|_| !normalized.is_empty())
+            .ok_or_else(invalid)?;
+        let number = |name| {
+            captures
+                .name(name)
+                .map_or(0, |number| number.as_str().parse::<i64>().unwrap())
+        };
+        Ok(Alarm {
+            before: Duration::days(number("days"))
+                + Duration::hours(number("hours"))
+                + Duration::minutes(number("minutes")),
+        })
+    }
+}
+
+impl fmt::Display for Alarm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = self.before.num_minutes();
+        let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+        let mut value = String::new();
+        for (amount, unit) in [(days, 'd'), (hours, 'h'), (minutes, 'm')] {
+            if amount > 0 {
+                value.push_str(&format!("{amount}{unit}"));
+            }
+        }
+        match value.is_empty() {
+            true => write!(f, "0m"),
+            false => write!(f, "{value}"),
+        }
+    }
+}
+
+impl FromStr for RotationMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "collection" => Ok(RotationMode::Collection),
+            "week" => Ok(RotationMode::Week),
+            _ => Err(format!("the rotation mode \"{value}\" is not supported")),
+        }
+    }
+}
+
+impl fmt::Display for RotationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RotationMode::Collection => write!(f, "collection"),
+            RotationMode::Week => write!(f, "week"),
+        }
+    }
+}
+
+impl Blackout {
+    /// Check whether a date is in this blackout.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from <= date && date <= self.until
+    }
+
+    /// Describe this blackout, e.g. "Abwesend vom 01.08.2024 bis 21.08.2024".
+    pub fn describe(&self) -> String {
+        format!(
+            "Abwesend vom {} bis {}",
+            self.from.format("%d.%m.%Y"),
+            self.until.format("%d.%m.%Y")
+        )
+    }
+}
+
+impl FromStr for Blackout {
+    type Err = String;
+
+    /// Parse a blackout, e.g. "2024-08-01..2024-08-21" or a single date like "2024-12-24".
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parse_date = |date: &str| {
+            NaiveDate::parse_from_str(date.trim(), FORMAT).map_err(|_| {
+                format!("the blackout \"{value}\" is not a date range like 2024-08-01..2024-08-21")
+            })
+        };
+        let (from, until) = match value.split_once("..") {
+            Some((from, until)) => (parse_date(from)?, parse_date(until)?),
+            None => (parse_date(value)?, parse_date(value)?),
+        };
+        if until < from {
+            return Err(format!("the blackout \"{value}\" ends before it starts"));
+        }
+        Ok(Blackout { from, until })
+    }
+}
+
+impl fmt::Display for Blackout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}..{}",
+            self.from.format(FORMAT),
+            self.until.format(FORMAT)
+        )
+    }
+}
+
+impl FromStr for BlackoutMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "drop" => Ok(BlackoutMode::Drop),
+            "mark" => Ok(BlackoutMode::Mark),
+            _ => Err(format!("the blackout mode \"{value}\" is not supported")),
+        }
+    }
+}
+
+impl fmt::Display for BlackoutMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlackoutMode::Drop => write!(f, "drop"),
+            BlackoutMode::Mark => write!(f, "mark"),
+        }
+    }
+}
+
+/// Parse comma-separated blackouts, e.g. "2024-08-01..2024-08-21,2024-12-24".
+pub fn parse_blackouts(value: &str) -> Result<Vec<Blackout>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|blackout| !blackout.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Parse comma-separated alarms, e.g. "1d,6h".
+pub fn parse_alarms(value: &str) -> Result<Vec<Alarm>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|alarm| !alarm.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Parse a time of day, e.g. "06:00" or "06:00:00".
+pub fn parse_time_of_day(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(value.trim(), "%H:%M:%S"))
+        .map_err(|_| format!("the time of day \"{value}\" is not a time like 06:00"))
+}
+
+/// Parse comma-separated assignees, e.g. "Anna,Ben <ben@example.org>".
+pub fn parse_assignees(value: &str) -> Result<Vec<Assignee>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|assignee| !assignee.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, NaiveTime};
+
+    use crate::{
+        garbage_client::tests::date,
+        render::{
+            parse_alarms, parse_assignees, parse_blackouts, parse_time_of_day, Alarm, Blackout,
+            BlackoutMode, RenderOptions, Rotation, RotationMode,
+        },
+    };
+
+    #[test]
+    fn test_parse_blackouts() {
+        assert_eq!(
+            parse_blackouts("2023-08-01..2023-08-21, 2023-12-24").unwrap(),
+            vec![
+                Blackout {
+                    from: date(8, 1),
+                    until: date(8, 21)
+                },
+                Blackout {
+                    from: date(12, 24),
+                    until: date(12, 24)
+                }
+            ]
+        );
+        assert!(parse_blackouts("2023-08-21..2023-08-01").is_err());
+        assert!(parse_blackouts("August").is_err());
+        assert_eq!(
+            "2023-08-01..2023-08-21"
+                .parse::<Blackout>()
+                .unwrap()
+                .to_string(),
+            "2023-08-01..2023-08-21"
+        );
+    }
+
+    #[test]
+    fn test_is_rendered() {
+        let today = date(7, 1);
+        let options = RenderOptions {
+            from: Some(date(7, 5)),
+            to: Some(date(9, 30)),
+            horizon_days: Some(60),
+            blackouts: parse_blackouts("2023-08-01..2023-08-21").unwrap(),
+            ..RenderOptions::default()
+        };
+        assert_eq!(options.until(today), Some(date(8, 30)));
+        assert!(!options.is_rendered(date(7, 4), today));
+        assert!(options.is_rendered(date(7, 5), today));
+        assert!(!options.is_rendered(date(8, 1), today));
+        assert!(options.is_rendered(date(8, 22), today));
+        assert!(!options.is_rendered(date(8, 31), today));
+
+        let options = RenderOptions {
+            blackout_mode: BlackoutMode::Mark,
+            ..options
+        };
+        assert!(options.is_rendered(date(8, 1), today));
+        assert!(options.is_marked(date(8, 1)));
+        assert_eq!(
+            options.marked(&[date(7, 31), date(8, 1), date(8, 15)]),
+            vec![(&options.blackouts[0], vec![date(8, 1), date(8, 15)])]
+        );
+    }
+
+    #[test]
+    fn test_rotation() {
+        let assignees = parse_assignees("Anna, Ben <ben@example.org>, Chris").unwrap();
+        assert_eq!(assignees[1].cal_address(), "mailto:ben@example.org");
+        assert_eq!(assignees[2].cal_address(), "invalid:nomail");
+        let rotation = Rotation {
+            assignees,
+            mode: RotationMode::Collection,
+            start: Some(date(7, 3)),
+        };
+        fn names<'a>(rotation: &'a Rotation, dates: &[NaiveDate]) -> Vec<&'a str> {
+            dates
+                .iter()
+                .map(|date| rotation.assignee(*date, dates).unwrap().name.as_str())
+                .collect()
+        }
+        // biweekly on Fridays, including the collections before the start
+        assert_eq!(
+            names(
+                &rotation,
+                &[date(6, 23), date(7, 7), date(7, 21), date(8, 4)]
+            ),
+            vec!["Chris", "Anna", "Ben", "Chris"]
+        );
+        // an additional collection is the turn of the next assignee
+        assert_eq!(
+            names(&rotation, &[date(7, 7), date(7, 14), date(7, 21)]),
+            vec!["Anna", "Ben", "Chris"]
+        );
+        // the spacing of the dates does not matter, e.g. four-weekly on Fridays
+        assert_eq!(
+            names(&rotation, &[date(7, 7), date(8, 4), date(9, 1)]),
+            vec!["Anna", "Ben", "Chris"]
+        );
+        // without a start, the collections are counted from a fixed Monday
+        let rotation = Rotation {
+            start: None,
+            ..rotation
+        };
+        assert_eq!(
+            names(&rotation, &[date(7, 21), date(7, 7), date(8, 4)]),
+            vec!["Ben", "Anna", "Chris"]
+        );
+        let rotation = Rotation {
+            mode: RotationMode::Week,
+            start: Some(date(7, 3)),
+            ..rotation
+        };
+        assert_eq!(
+            names(
+                &rotation,
+                &[date(7, 5), date(7, 9), date(7, 10), date(7, 21)]
+            ),
+            vec!["Anna", "Anna", "Ben", "Chris"]
+        );
+        assert!("Anna \"A\"".parse::<crate::render::Assignee>().is_err());
+    }
+
+    /// Test whether the assignments stay the same when the known collections move on by one collection.
+    #[test]
+    fn test_rotation_moving_window() {
+        let assignees = parse_assignees("Anna, Ben, Chris").unwrap();
+        let dates: Vec<NaiveDate> = (0..10)
+            .map(|index| date(6, 2) + Duration::days(14 * index))
+            .collect();
+        for start in [None, Some(date(5, 1)), Some(date(7, 12))] {
+            let rotation = Rotation {
+                assignees: assignees.clone(),
+                mode: RotationMode::Collection,
+                start,
+            };
+            let names = |window: &[NaiveDate]| -> Vec<String> {
+                window
+                    .iter()
+                    .map(|date| rotation.assignee(*date, window).unwrap().name.clone())
+                    .collect()
+            };
+            let all = names(&dates);
+            for offset in 1..5 {
+                assert_eq!(
+                    names(&dates[offset..offset + 5]),
+                    all[offset..offset + 5],
+                    "the start is {start:?} and the window moved by {offset}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_alarms() {
+        let alarms = parse_alarms("6h, 1d, 1d12h30m, 0m").unwrap();
+        let triggers: Vec<String> = alarms.iter().map(Alarm::trigger).collect();
+        assert_eq!(triggers, vec!["-PT6H", "-P1D", "-P1DT12H30M", "-PT0M"]);
+        let values: Vec<String> = alarms.iter().map(Alarm::to_string).collect();
+        assert_eq!(values, vec!["6h", "1d", "1d12h30m", "0m"]);
+        assert!("".parse::<Alarm>().is_err());
+        assert!("6 hours".parse::<Alarm>().is_err());
+        assert!("-6h".parse::<Alarm>().is_err());
+    }
+
+    #[test]
+    fn test_parse_time_of_day() {
+        let time = NaiveTime::from_hms_opt(6, 30, 0).unwrap();
+        assert_eq!(parse_time_of_day("06:30"), Ok(time));
+        assert_eq!(parse_time_of_day("6:30:00"), Ok(time));
+        assert!(parse_time_of_day("6 Uhr").is_err());
+    }
+}
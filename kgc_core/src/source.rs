@@ -0,0 +1,383 @@
+//! This module provides the sources from which the waste data of an address can be read.
+//!
+//! The official website is the primary source, the other sources keep the calendar available when it is down.
+
+use std::{fmt, fs, path::PathBuf, str::FromStr, sync::Arc};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use chrono::{Duration, Local, NaiveDate};
+use serde::Deserialize;
+
+use crate::{
+    address::{Address, AddressError},
+    archive::{Archive, ArchivingSource},
+    garbage_client::{WasteData, WasteType},
+    official_calendar,
+    provider::Provider,
+};
+
+/// A source of waste data.
+#[async_trait]
+pub trait ScheduleSource: fmt::Debug + Send + Sync {
+    /// Fetch the waste data for a specific address.
+    ///
+    /// The resolved address is returned alongside the waste data.
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)>;
+}
+
+#[async_trait]
+impl<T: ScheduleSource + ?Sized> ScheduleSource for Arc<T> {
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+        self.as_ref().fetch(address).await
+    }
+}
+
+/// The official website of a provider, which is scraped live.
+#[derive(Debug, Clone)]
+pub struct WebsiteSource {
+    pub provider: Arc<dyn Provider>,
+}
+
+#[async_trait]
+impl ScheduleSource for WebsiteSource {
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+        self.provider.fetch(address).await
+    }
+}
+
+/// A saved HTML response of the official website of a provider.
+#[derive(Debug, Clone)]
+pub struct HtmlFileSource {
+    pub provider: Arc<dyn Provider>,
+    pub path: PathBuf,
+}
+
+#[async_trait]
+impl ScheduleSource for HtmlFileSource {
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+        let html = fs::read_to_string(&self.path)?;
+        let waste_data = self.provider.parse_response(address, &html)?;
+        Ok((address.clone(), waste_data))
+    }
+}
+
+/// The official iCalendar download.
+///
+/// The placeholders `{street}` and `{street_number}` of the location are replaced by the address.
+#[derive(Debug, Clone)]
+pub struct OfficialCalendarSource {
+    pub location: String,
+    /// The number of days from today until which recurring rules are expanded.
+    pub days: i64,
+}
+
+#[async_trait]
+impl ScheduleSource for OfficialCalendarSource {
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+        let location = self
+            .location
+            .replace("{street}", &address.street)
+            .replace("{street_number}", &address.house_number.upstream());
+        let until = Local::now().date_naive() + Duration::days(self.days);
+        let waste_data = official_calendar::get(&location, until).await?;
+        Ok((address.clone(), waste_data))
+    }
+}
+
+/// A static dataset of waste data, e.g. for demonstrations or as a last resort.
+#[derive(Debug, Clone, Default)]
+pub struct StaticSource {
+    pub entries: Vec<StaticEntry>,
+}
+
+/// The waste data of a single address in a static dataset.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct StaticEntry {
+    pub street: String,
+    pub street_number: String,
+    pub residual_waste: Vec<NaiveDate>,
+    pub organic_waste: Vec<NaiveDate>,
+    pub recyclable_waste: Vec<NaiveDate>,
+    pub paper_waste: Vec<NaiveDate>,
+    pub bulky_waste: Vec<NaiveDate>,
+    pub hazardous_waste: Vec<NaiveDate>,
+}
+
+impl StaticSource {
+    /// Read a static dataset from a JSON file with a list of entries.
+    pub fn read(path: impl Into<PathBuf>) -> Result<Self> {
+        let entries = serde_json::from_str(&fs::read_to_string(path.into())?)?;
+        Ok(StaticSource { entries })
+    }
+}
+
+#[async_trait]
+impl ScheduleSource for StaticSource {
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+        let Some(entry) = self
+            .entries
+            .iter()
+            .find(|entry| address.matches(&entry.street, &entry.street_number))
+        else {
+            bail!("the static dataset does not contain the address \"{address}\"");
+        };
+        let mut waste_data = WasteData {
+            residual_waste: entry.residual_waste.clone(),
+            organic_waste: entry.organic_waste.clone(),
+            recyclable_waste: entry.recyclable_waste.clone(),
+            paper_waste: entry.paper_waste.clone(),
+            bulky_waste: entry.bulky_waste.clone(),
+            hazardous_waste: entry.hazardous_waste.clone(),
+            ..WasteData::default()
+        };
+        for waste_type in WasteType::ALL {
+            waste_data.dates_mut(waste_type).sort();
+        }
+        Ok((address.clone(), waste_data))
+    }
+}
+
+/// A composite source which tries its sources in order until one succeeds.
+#[derive(Debug, Default)]
+pub struct FallbackSource {
+    pub sources: Vec<Box<dyn ScheduleSource>>,
+}
+
+#[async_trait]
+impl ScheduleSource for FallbackSource {
+    /// Fetch the waste data from the first source which succeeds.
+    ///
+    /// If all sources fail, the error of the first source is returned, because it is the most relevant one.
+    /// An invalid address fails immediately, because the other sources cannot resolve it either.
+    async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+        let mut first_error = None;
+        for source in &self.sources {
+            match source.fetch(address).await {
+                Ok(result) => return Ok(result),
+                Err(err) if err.is::<AddressError>() => return Err(err),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        Err(first_error.unwrap_or_else(|| anyhow!("no schedule source is configured")))
+    }
+}
+
+/// A kind of source, which is used to configure a source chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    Website,
+    Html,
+    Official,
+    Static,
+    /// The history of the archive, see [`crate::archive::Archive`].
+    Archive,
+}
+
+/// The configuration of the sources of a source chain.
+#[derive(Debug, Clone, Default)]
+pub struct SourceConfig {
+    pub html_file: Option<PathBuf>,
+    pub official_calendar: Option<String>,
+    pub official_calendar_days: i64,
+    pub static_dataset: Option<PathBuf>,
+    /// The archive, into which the waste data of all other sources is merged.
+    pub archive: Option<Arc<Archive>>,
+}
+
+impl SourceConfig {
+    /// Build a source chain for a provider which tries the sources of specific kinds in order.
+    ///
+    /// With an archive, the waste data of the other sources is merged into it,
+    /// and its history is the last resort unless it is placed in the chain explicitly.
+    pub fn chain(
+        &self,
+        provider: Arc<dyn Provider>,
+        kinds: &[SourceKind],
+    ) -> Result<FallbackSource> {
+        let mut kinds = kinds.to_vec();
+        if self.archive.is_some() && !kinds.contains(&SourceKind::Archive) {
+            kinds.push(SourceKind::Archive);
+        }
+        let mut sources: Vec<Box<dyn ScheduleSource>> = vec![];
+        for kind in &kinds {
+            let source: Box<dyn ScheduleSource> = match kind {
+                SourceKind::Website => Box::new(WebsiteSource {
+                    provider: provider.clone(),
+                }),
+                SourceKind::Html => {
+                    let Some(path) = &self.html_file else {
+                        bail!("the source \"{kind}\" requires an HTML file");
+                    };
+                    Box::new(HtmlFileSource {
+                        provider: provider.clone(),
+                        path: path.clone(),
+                    })
+                }
+                SourceKind::Official => {
+                    let Some(location) = &self.official_calendar else {
+                        bail!(
+                            "the source \"{kind}\" requires the location of the official calendar"
+                        );
+                    };
+                    Box::new(OfficialCalendarSource {
+                        location: location.clone(),
+                        days: self.official_calendar_days,
+                    })
+                }
+                SourceKind::Static => {
+                    let Some(path) = &self.static_dataset else {
+                        bail!("the source \"{kind}\" requires a static dataset");
+                    };
+                    Box::new(StaticSource::read(path)?)
+                }
+                SourceKind::Archive => {
+                    let Some(archive) = &self.archive else {
+                        bail!("the source \"{kind}\" requires an archive");
+                    };
+                    sources.push(Box::new(archive.clone()));
+                    continue;
+                }
+            };
+            sources.push(match &self.archive {
+                Some(archive) => Box::new(ArchivingSource {
+                    source,
+                    archive: archive.clone(),
+                }),
+                None => source,
+            });
+        }
+        Ok(FallbackSource { sources })
+    }
+}
+
+impl FromStr for SourceKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "website" | "live" => Ok(SourceKind::Website),
+            "html" => Ok(SourceKind::Html),
+            "official" | "ics" => Ok(SourceKind::Official),
+            "static" => Ok(SourceKind::Static),
+            "archive" => Ok(SourceKind::Archive),
+            _ => Err(format!("the source \"{value}\" is not supported")),
+        }
+    }
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceKind::Website => write!(f, "website"),
+            SourceKind::Html => write!(f, "html"),
+            SourceKind::Official => write!(f, "official"),
+            SourceKind::Static => write!(f, "static"),
+            SourceKind::Archive => write!(f, "archive"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, fs, sync::Arc};
+
+    use anyhow::Result;
+    use async_trait::async_trait;
+
+    use crate::{
+        address::{Address, AddressError},
+        garbage_client::{tests::date, WasteData},
+        provider::karlsruhe::KarlsruheProvider,
+        source::{FallbackSource, HtmlFileSource, ScheduleSource, StaticSource},
+    };
+
+    /// Test whether a saved HTML response is parsed and verified.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_html_file_source() {
+        let html = include_str!("provider/karlsruhe/tests/response.html").replace(
+            "strasse=<REDACTED>&hausnr=<REDACTED>",
+            "strasse=Schlo%C3%9Fplatz&hausnr=1",
+        );
+        let path = temp_dir().join("kgc_test_html_file_source.html");
+        fs::write(&path, html).unwrap();
+        let source = HtmlFileSource {
+            provider: Arc::new(KarlsruheProvider::default()),
+            path,
+        };
+
+        let address = Address::new("Schlossplatz", "1").unwrap();
+        let (_, waste_data) = source.fetch(&address).await.unwrap();
+        assert_eq!(
+            waste_data.residual_waste,
+            vec![date(6, 16), date(6, 29), date(7, 14)]
+        );
+        let address = Address::new("Schlossplatz", "2").unwrap();
+        assert!(source.fetch(&address).await.is_err());
+    }
+
+    /// Test whether the sources are tried in order.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_fallback_source() {
+        let static_source: StaticSource = StaticSource {
+            entries: serde_json::from_str(include_str!("source/tests/static.json")).unwrap(),
+        };
+        let source = FallbackSource {
+            sources: vec![
+                Box::new(HtmlFileSource {
+                    provider: Arc::new(KarlsruheProvider::default()),
+                    path: temp_dir().join("kgc_test_missing.html"),
+                }),
+                Box::new(static_source),
+            ],
+        };
+        let address = Address::new("schlossplatz", "1").unwrap();
+        let (_, waste_data) = source.fetch(&address).await.unwrap();
+        assert_eq!(waste_data.organic_waste, vec![date(6, 7), date(6, 14)]);
+        assert_eq!(waste_data.bulky_waste, vec![]);
+
+        let address = Address::new("Kaiserstraße", "1").unwrap();
+        let err = source.fetch(&address).await.unwrap_err();
+        assert!(err.is::<std::io::Error>());
+
+        let source = FallbackSource::default();
+        assert!(source.fetch(&address).await.is_err());
+    }
+
+    /// A source which does not know any street.
+    #[derive(Debug)]
+    struct UnknownStreetSource;
+
+    #[async_trait]
+    impl ScheduleSource for UnknownStreetSource {
+        async fn fetch(&self, address: &Address) -> Result<(Address, WasteData)> {
+            Err(AddressError::UnknownStreet(address.street.clone()).into())
+        }
+    }
+
+    /// Test whether an invalid address is not looked up in the following sources.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_fallback_source_with_invalid_address() {
+        let source = FallbackSource {
+            sources: vec![
+                Box::new(UnknownStreetSource),
+                Box::new(StaticSource {
+                    entries: serde_json::from_str(include_str!("source/tests/static.json"))
+                        .unwrap(),
+                }),
+            ],
+        };
+        let address = Address::new("Schlossplatz", "1").unwrap();
+        let err = source.fetch(&address).await.unwrap_err();
+        assert!(err.is::<AddressError>());
+    }
+}
@@ -1,13 +1,40 @@
+use gloo_net::http::Request;
+use serde::Deserialize;
 use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
 use web_sys::{window, HtmlInputElement, Url, UrlSearchParams};
 use yew::prelude::*;
 
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Notice {
+    text: String,
+    links: Vec<String>,
+}
+
 #[function_component]
 pub fn App() -> Html {
+    let notices_handle = use_state_eq(Vec::<Notice>::new);
+    let notices = (*notices_handle).clone();
+    use_effect_with_deps(
+        move |_| {
+            spawn_local(async move {
+                let Ok(response) = Request::get("/notices").send().await else {
+                    return;
+                };
+                if let Ok(notices) = response.json::<Vec<Notice>>().await {
+                    notices_handle.set(notices);
+                }
+            });
+        },
+        (),
+    );
+
     let street_handle = use_state_eq(|| String::from(""));
     let street = (*street_handle).clone();
     let street_number_handle = use_state_eq(|| String::from(""));
     let street_number = (*street_number_handle).clone();
+    let district_handle = use_state_eq(|| String::from(""));
+    let district = (*district_handle).clone();
 
     let exclude_residual_handle = use_state_eq(|| false);
     let exclude_residual = *exclude_residual_handle;
@@ -19,13 +46,19 @@ pub fn App() -> Html {
     let exclude_paper = *exclude_paper_handle;
     let exclude_bulky_handle = use_state_eq(|| false);
     let exclude_bulky = *exclude_bulky_handle;
+    let exclude_hazardous_handle = use_state_eq(|| false);
+    let exclude_hazardous = *exclude_hazardous_handle;
 
-    let calendar_url_search_params = |street: &str, street_number: &str| -> UrlSearchParams {
-        let url_search_params = UrlSearchParams::new().unwrap();
-        url_search_params.set("street", street);
-        url_search_params.set("street_number", street_number);
-        url_search_params
-    };
+    let calendar_url_search_params =
+        |street: &str, street_number: &str, district: &str| -> UrlSearchParams {
+            let url_search_params = UrlSearchParams::new().unwrap();
+            url_search_params.set("street", street);
+            url_search_params.set("street_number", street_number);
+            if !district.is_empty() {
+                url_search_params.set("district", district);
+            }
+            url_search_params
+        };
     let calendar_url = |path: &str, url_search_params: UrlSearchParams| -> String {
         let url = Url::new_with_base(
             path,
@@ -35,22 +68,25 @@ pub fn App() -> Html {
         url.set_search(&String::from(url_search_params.to_string()));
         String::from(url.to_string())
     };
-    let specific_calendar_url = |street: &str, street_number: &str, r#type: &str| -> String {
-        let url_search_params = calendar_url_search_params(street, street_number);
-        calendar_url(&format!("/calendar/{}", r#type), url_search_params)
-    };
+    let specific_calendar_url =
+        |street: &str, street_number: &str, district: &str, r#type: &str| -> String {
+            let url_search_params = calendar_url_search_params(street, street_number, district);
+            calendar_url(&format!("/calendar/{}", r#type), url_search_params)
+        };
 
     let main_url_handle = use_memo(
         |(
             street,
             street_number,
+            district,
             exclude_residual,
             exclude_organic,
             exclude_recyclable,
             exclude_paper,
             exclude_bulky,
+            exclude_hazardous,
         )| {
-            let url_search_params = calendar_url_search_params(street, street_number);
+            let url_search_params = calendar_url_search_params(street, street_number, district);
             if *exclude_residual {
                 url_search_params.set("exclude_residual", "true");
             }
@@ -66,45 +102,67 @@ pub fn App() -> Html {
             if *exclude_bulky {
                 url_search_params.set("exclude_bulky", "true");
             }
+            if *exclude_hazardous {
+                url_search_params.set("exclude_hazardous", "true");
+            }
             calendar_url("/calendar", url_search_params)
         },
         (
             street.clone(),
             street_number.clone(),
+            district.clone(),
             exclude_residual,
             exclude_organic,
             exclude_recyclable,
             exclude_paper,
             exclude_bulky,
+            exclude_hazardous,
         ),
     );
     let main_url = (*main_url_handle).clone();
 
     let residual_url_handle = use_memo(
-        |(street, street_number)| specific_calendar_url(street, street_number, "residual"),
-        (street.clone(), street_number.clone()),
+        |(street, street_number, district)| {
+            specific_calendar_url(street, street_number, district, "residual")
+        },
+        (street.clone(), street_number.clone(), district.clone()),
     );
     let residual_url = (*residual_url_handle).clone();
     let organic_url_handle = use_memo(
-        |(street, street_number)| specific_calendar_url(street, street_number, "organic"),
-        (street.clone(), street_number.clone()),
+        |(street, street_number, district)| {
+            specific_calendar_url(street, street_number, district, "organic")
+        },
+        (street.clone(), street_number.clone(), district.clone()),
     );
     let organic_url = (*organic_url_handle).clone();
     let recyclable_url_handle = use_memo(
-        |(street, street_number)| specific_calendar_url(street, street_number, "recyclable"),
-        (street.clone(), street_number.clone()),
+        |(street, street_number, district)| {
+            specific_calendar_url(street, street_number, district, "recyclable")
+        },
+        (street.clone(), street_number.clone(), district.clone()),
     );
     let recyclable_url = (*recyclable_url_handle).clone();
     let paper_url_handle = use_memo(
-        |(street, street_number)| specific_calendar_url(street, street_number, "paper"),
-        (street.clone(), street_number.clone()),
+        |(street, street_number, district)| {
+            specific_calendar_url(street, street_number, district, "paper")
+        },
+        (street.clone(), street_number.clone(), district.clone()),
     );
     let paper_url = (*paper_url_handle).clone();
     let bulky_url_handle = use_memo(
-        |(street, street_number)| specific_calendar_url(street, street_number, "bulky"),
-        (street.clone(), street_number.clone()),
+        |(street, street_number, district)| {
+            specific_calendar_url(street, street_number, district, "bulky")
+        },
+        (street.clone(), street_number.clone(), district.clone()),
     );
     let bulky_url = (*bulky_url_handle).clone();
+    let hazardous_url_handle = use_memo(
+        |(street, street_number, district)| {
+            specific_calendar_url(street, street_number, district, "hazardous")
+        },
+        (street.clone(), street_number.clone(), district.clone()),
+    );
+    let hazardous_url = (*hazardous_url_handle).clone();
 
     let on_input_street = Callback::from(move |e: InputEvent| {
         street_handle.set(
@@ -122,6 +180,14 @@ pub fn App() -> Html {
                 .value(),
         )
     });
+    let on_input_district = Callback::from(move |e: InputEvent| {
+        district_handle.set(
+            e.target()
+                .unwrap()
+                .unchecked_into::<HtmlInputElement>()
+                .value(),
+        )
+    });
     let on_input_exclude_residual = Callback::from(move |e: InputEvent| {
         exclude_residual_handle.set(
             e.target()
@@ -162,9 +228,29 @@ pub fn App() -> Html {
                 .checked(),
         )
     });
+    let on_input_exclude_hazardous = Callback::from(move |e: InputEvent| {
+        exclude_hazardous_handle.set(
+            e.target()
+                .unwrap()
+                .unchecked_into::<HtmlInputElement>()
+                .checked(),
+        )
+    });
 
     html! {
         <main>
+            if !notices.is_empty() {
+                <aside>
+                    { for notices.iter().map(|notice| html! {
+                        <p>
+                            {notice.text.clone()}
+                            { for notice.links.iter().map(|link| html! {
+                                <>{" "}<a href={link.clone()}>{link.clone()}</a></>
+                            }) }
+                        </p>
+                    }) }
+                </aside>
+            }
             <div>
                 <label>{"Street"}<br/><input
                     oninput={on_input_street}
@@ -181,6 +267,14 @@ pub fn App() -> Html {
                     value={street_number.clone()}
                 /></label>
             </div>
+            <div>
+                <label>{"District (optional)"}<br/><input
+                    oninput={on_input_district}
+                    name="district"
+                    placeholder="Innenstadt-West"
+                    value={district.clone()}
+                /></label>
+            </div>
             <fieldset>
                 <legend>{"Excluded waste types"}</legend>
                 <div>
@@ -233,6 +327,16 @@ pub fn App() -> Html {
                         />{"Bulky"}</label
                     >
                 </div>
+                <div>
+                    <label>
+                        <input
+                            oninput={on_input_exclude_hazardous}
+                            name="exclude_hazardous"
+                            type="checkbox"
+                            checked={exclude_hazardous}
+                        />{"Hazardous"}</label
+                    >
+                </div>
             </fieldset>
             <output>
                 <div>
@@ -277,6 +381,13 @@ pub fn App() -> Html {
                         style="width:100%"
                     /></label>
                 </div>
+                <div>
+                    <label>{"Hazardous URL"}<br/><input
+                        readonly=true
+                        value={hazardous_url.clone()}
+                        style="width:100%"
+                    /></label>
+                </div>
             </output>
         </main>
     }
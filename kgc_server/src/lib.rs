@@ -0,0 +1,104 @@
+//! This crate implements the iCalendar server, which is started by the subcommand `serve` of the `kgc` binary.
+
+use std::{net::SocketAddr, path::PathBuf};
+
+use anyhow::Result;
+use axum::{routing::get, Router};
+use tower_http::services::{ServeDir, ServeFile};
+
+use crate::state::AppState;
+
+pub mod route;
+pub mod state;
+
+#[cfg(debug_assertions)]
+const SERVE_DIR: &str = "kgc_server/frontend/dist";
+#[cfg(not(debug_assertions))]
+const SERVE_DIR: &str = "dist";
+
+/// The options of the server.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The port the server binds to.
+    pub port: u16,
+    /// The URL of the official website, which replaces the one of the default provider.
+    pub upstream_url: Option<String>,
+    /// The directory of the archive, which takes precedence over `KGC_ARCHIVE_DIR`.
+    pub archive_directory: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            port: 8008,
+            upstream_url: None,
+            archive_directory: None,
+        }
+    }
+}
+
+/// Build the router with all routes and the frontend.
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/calendar", get(route::calendar::handler))
+        .route(
+            "/calendar/residual",
+            get(route::calendar::residual::handler),
+        )
+        .route("/calendar/organic", get(route::calendar::organic::handler))
+        .route(
+            "/calendar/recyclable",
+            get(route::calendar::recyclable::handler),
+        )
+        .route("/calendar/paper", get(route::calendar::paper::handler))
+        .route("/calendar/bulky", get(route::calendar::bulky::handler))
+        .route(
+            "/calendar/hazardous",
+            get(route::calendar::hazardous::handler),
+        )
+        .route("/calendar/:provider", get(route::calendar::handler))
+        .route(
+            "/calendar/:provider/residual",
+            get(route::calendar::residual::handler),
+        )
+        .route(
+            "/calendar/:provider/organic",
+            get(route::calendar::organic::handler),
+        )
+        .route(
+            "/calendar/:provider/recyclable",
+            get(route::calendar::recyclable::handler),
+        )
+        .route(
+            "/calendar/:provider/paper",
+            get(route::calendar::paper::handler),
+        )
+        .route(
+            "/calendar/:provider/bulky",
+            get(route::calendar::bulky::handler),
+        )
+        .route(
+            "/calendar/:provider/hazardous",
+            get(route::calendar::hazardous::handler),
+        )
+        .route("/facilities", get(route::facilities::handler))
+        .route(
+            "/facilities/calendar",
+            get(route::facilities::calendar::handler),
+        )
+        .route("/notices", get(route::notices::handler))
+        .route("/notices/:provider", get(route::notices::handler))
+        .route_service("/*path", ServeDir::new(SERVE_DIR))
+        .route_service("/", ServeFile::new(format!("{}/index.html", SERVE_DIR)))
+        .with_state(state)
+}
+
+/// Serve until the server fails.
+pub async fn serve(options: Options) -> Result<()> {
+    let app = router(AppState::from_env(&options));
+    let addr = SocketAddr::from(([0, 0, 0, 0], options.port));
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
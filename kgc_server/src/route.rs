@@ -1 +1,22 @@
 pub mod calendar;
+pub mod facilities;
+pub mod notices;
+
+use std::ops::RangeInclusive;
+
+/// The largest number of days which can be requested.
+///
+/// The dates of a request must not overflow and a single request must not keep the server busy for long.
+pub const MAX_DAYS: i64 = 730;
+
+/// Check whether the number of days of a query parameter is within a range.
+pub fn check_days(name: &str, days: i64, range: RangeInclusive<i64>) -> Result<i64, String> {
+    if !range.contains(&days) {
+        return Err(format!(
+            "the query parameter {name} must be between {} and {}",
+            range.start(),
+            range.end()
+        ));
+    }
+    Ok(days)
+}
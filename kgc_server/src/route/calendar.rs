@@ -1,17 +1,34 @@
 pub mod bulky;
+pub mod hazardous;
 pub mod organic;
 pub mod paper;
 pub mod recyclable;
 pub mod residual;
 
 use axum::{
-    extract::Query,
+    extract::{Path, Query, State},
     http::{header::CONTENT_TYPE, StatusCode},
     response::{IntoResponse, Response},
 };
-use kgc_core::{garbage_client, garbage_client::WasteTypeBitmask, ical::generator::Emitter};
+use chrono::{Duration, Local, NaiveDate};
+use kgc_core::{
+    address::{Address, AddressError},
+    garbage_client,
+    garbage_client::{AddressMismatchError, WasteTypeBitmask},
+    ical::generator::Emitter,
+    prediction,
+    render::{
+        parse_alarms, parse_assignees, parse_blackouts, parse_time_of_day, RenderOptions, Rotation,
+    },
+    source::ScheduleSource,
+};
 use serde::Deserialize;
 
+use crate::{
+    route::{check_days, MAX_DAYS},
+    state::AppState,
+};
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct QueryParams {
     #[serde(flatten)]
@@ -26,12 +43,87 @@ pub struct QueryParams {
     exclude_paper: bool,
     #[serde(default)]
     exclude_bulky: bool,
+    #[serde(default)]
+    exclude_hazardous: bool,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct StreetQueryParams {
     street: String,
     street_number: String,
+    district: Option<String>,
+}
+
+/// The options of the rendered calendar.
+///
+/// They are extracted separately, because a flattened struct can not deserialize numbers from a query string.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RenderQueryParams {
+    predict_days: Option<i64>,
+    #[serde(default)]
+    mark_shifted: bool,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    horizon_days: Option<i64>,
+    skip: Option<String>,
+    skip_mode: Option<String>,
+    rotation: Option<String>,
+    rotation_mode: Option<String>,
+    rotation_start: Option<NaiveDate>,
+    alarm: Option<String>,
+    time_of_day: Option<String>,
+}
+
+impl TryFrom<&RenderQueryParams> for RenderOptions {
+    type Error = String;
+
+    fn try_from(value: &RenderQueryParams) -> Result<Self, Self::Error> {
+        Ok(RenderOptions {
+            mark_shifted: value.mark_shifted,
+            from: value.from,
+            to: value.to,
+            horizon_days: value
+                .horizon_days
+                .map(|horizon_days| check_days("horizon_days", horizon_days, 0..=MAX_DAYS))
+                .transpose()?,
+            blackouts: value
+                .skip
+                .as_deref()
+                .map(parse_blackouts)
+                .transpose()?
+                .unwrap_or_default(),
+            blackout_mode: value
+                .skip_mode
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+            rotation: match value.rotation.as_deref().map(parse_assignees).transpose()? {
+                Some(assignees) if !assignees.is_empty() => Some(Rotation {
+                    assignees,
+                    mode: value
+                        .rotation_mode
+                        .as_deref()
+                        .map(str::parse)
+                        .transpose()?
+                        .unwrap_or_default(),
+                    start: value.rotation_start,
+                }),
+                _ => None,
+            },
+            alarms: value
+                .alarm
+                .as_deref()
+                .map(parse_alarms)
+                .transpose()?
+                .unwrap_or_default(),
+            time_of_day: value
+                .time_of_day
+                .as_deref()
+                .map(parse_time_of_day)
+                .transpose()?,
+        })
+    }
 }
 
 impl From<&QueryParams> for WasteTypeBitmask {
@@ -52,21 +144,69 @@ impl From<&QueryParams> for WasteTypeBitmask {
         if value.exclude_bulky {
             waste_type_bitmask |= WasteTypeBitmask::Bulky;
         }
+        if value.exclude_hazardous {
+            waste_type_bitmask |= WasteTypeBitmask::Hazardous;
+        }
         waste_type_bitmask
     }
 }
 
 pub async fn handle(
+    state: &AppState,
+    provider_id: Option<&str>,
     street_query_params: &StreetQueryParams,
+    render_query_params: &RenderQueryParams,
     excluded_waste_types: WasteTypeBitmask,
 ) -> Result<Response, (StatusCode, String)> {
-    let ical_calendar = garbage_client::get(
+    let render_options = RenderOptions::try_from(render_query_params)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err))?;
+    let predict_days = render_query_params
+        .predict_days
+        .map(|predict_days| check_days("predict_days", predict_days, 0..=MAX_DAYS))
+        .transpose()
+        .map_err(|err| (StatusCode::BAD_REQUEST, err))?;
+    let address = Address::new(
         &street_query_params.street,
         &street_query_params.street_number,
-        excluded_waste_types,
     )
-    .await
-    .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?
+    .with_district(street_query_params.district.as_deref());
+    let Some(provider_state) = state.provider(provider_id) else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!(
+                "the provider \"{}\" is unknown",
+                provider_id.unwrap_or_default()
+            ),
+        ));
+    };
+    let (address, mut waste_data) = provider_state.source.fetch(&address).await.map_err(|err| {
+        let status_code = if err.is::<AddressError>() {
+            StatusCode::BAD_REQUEST
+        } else if err.is::<AddressMismatchError>() {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        (status_code, err.to_string())
+    })?;
+    for warning in waste_data.warnings.drain(..) {
+        eprintln!("Warning: {address}: {warning}");
+    }
+    if let Some(predict_days) = predict_days {
+        prediction::predict(
+            &mut waste_data,
+            Local::now().date_naive() + Duration::days(predict_days),
+        );
+    }
+    let ical_calendar = garbage_client::get_calendar(
+        provider_state.provider.as_ref(),
+        &address.requested.0,
+        &address.requested.1,
+        waste_data,
+        excluded_waste_types,
+        &render_options,
+    );
     let response = ([(CONTENT_TYPE, "text/calendar")], ical_calendar.generate()).into_response();
     Ok(response)
 }
@@ -74,11 +214,19 @@ pub async fn handle(
 /// Handle calendar requests.
 ///
 /// The `street` and `street_number` must be given in the query string.
+/// The provider is selected by the path, e.g. `/calendar/karlsruhe`, and defaults to Karlsruhe.
 pub async fn handler(
+    State(state): State<AppState>,
+    provider: Option<Path<String>>,
     Query(query_params): Query<QueryParams>,
+    Query(render_query_params): Query<RenderQueryParams>,
 ) -> Result<Response, (StatusCode, String)> {
+    let provider_id = provider.map(|Path(provider)| provider);
     let response = handle(
+        &state,
+        provider_id.as_deref(),
         &query_params.street_query_params,
+        &render_query_params,
         WasteTypeBitmask::from(&query_params),
     )
     .await?;
@@ -87,7 +235,11 @@ pub async fn handler(
 
 #[cfg(test)]
 mod tests {
-    use kgc_core::garbage_client::WasteTypeBitmask;
+    use chrono::NaiveTime;
+    use kgc_core::{
+        garbage_client::WasteTypeBitmask,
+        render::{BlackoutMode, RotationMode},
+    };
 
     use super::*;
 
@@ -97,12 +249,14 @@ mod tests {
             street_query_params: StreetQueryParams {
                 street: "".to_string(),
                 street_number: "".to_string(),
+                district: None,
             },
             exclude_residual: false,
             exclude_organic: false,
             exclude_recyclable: false,
             exclude_paper: false,
             exclude_bulky: false,
+            exclude_hazardous: false,
         };
         let exclude_from_query_params = WasteTypeBitmask::from(&exclude_query_params);
         assert_eq!(exclude_from_query_params, WasteTypeBitmask::none());
@@ -110,12 +264,14 @@ mod tests {
             street_query_params: StreetQueryParams {
                 street: "".to_string(),
                 street_number: "".to_string(),
+                district: None,
             },
             exclude_residual: true,
             exclude_organic: false,
             exclude_recyclable: false,
             exclude_paper: false,
             exclude_bulky: false,
+            exclude_hazardous: false,
         };
         let exclude_from_query_params = WasteTypeBitmask::from(&exclude_query_params);
         assert_eq!(exclude_from_query_params, WasteTypeBitmask::Residual);
@@ -123,12 +279,14 @@ mod tests {
             street_query_params: StreetQueryParams {
                 street: "".to_string(),
                 street_number: "".to_string(),
+                district: None,
             },
             exclude_residual: false,
             exclude_organic: true,
             exclude_recyclable: false,
             exclude_paper: false,
             exclude_bulky: false,
+            exclude_hazardous: false,
         };
         let exclude_from_query_params = WasteTypeBitmask::from(&exclude_query_params);
         assert_eq!(exclude_from_query_params, WasteTypeBitmask::Organic);
@@ -136,12 +294,14 @@ mod tests {
             street_query_params: StreetQueryParams {
                 street: "".to_string(),
                 street_number: "".to_string(),
+                district: None,
             },
             exclude_residual: false,
             exclude_organic: false,
             exclude_recyclable: true,
             exclude_paper: true,
             exclude_bulky: true,
+            exclude_hazardous: true,
         };
         let exclude_from_query_params = WasteTypeBitmask::from(&exclude_query_params);
         assert_eq!(
@@ -149,6 +309,100 @@ mod tests {
             WasteTypeBitmask::Recyclable
                 .or(WasteTypeBitmask::Paper)
                 .or(WasteTypeBitmask::Bulky)
+                .or(WasteTypeBitmask::Hazardous)
         );
     }
+
+    #[test]
+    fn test_try_from_render_query_params_for_render_options() {
+        let render_query_params = RenderQueryParams {
+            skip: Some("2024-08-01..2024-08-21".to_string()),
+            skip_mode: Some("mark".to_string()),
+            rotation: Some("Anna,Ben".to_string()),
+            rotation_mode: Some("week".to_string()),
+            alarm: Some("1d,6h".to_string()),
+            time_of_day: Some("06:00".to_string()),
+            ..RenderQueryParams::default()
+        };
+        let render_options = RenderOptions::try_from(&render_query_params).unwrap();
+        assert_eq!(render_options.alarms.len(), 2);
+        assert_eq!(render_options.time_of_day, NaiveTime::from_hms_opt(6, 0, 0));
+        assert_eq!(render_options.blackouts.len(), 1);
+        assert_eq!(render_options.blackout_mode, BlackoutMode::Mark);
+        let rotation = render_options.rotation.unwrap();
+        assert_eq!(rotation.assignees.len(), 2);
+        assert_eq!(rotation.mode, RotationMode::Week);
+        let render_query_params = RenderQueryParams {
+            skip: Some("August".to_string()),
+            ..RenderQueryParams::default()
+        };
+        assert!(RenderOptions::try_from(&render_query_params).is_err());
+    }
+
+    /// Get the status code of an unsuccessful calendar request for the given render options.
+    async fn status(render_query_params: RenderQueryParams) -> StatusCode {
+        let street_query_params = StreetQueryParams {
+            street: "Kaiserstraße".to_string(),
+            street_number: "1".to_string(),
+            district: None,
+        };
+        let (status, _) = handle(
+            &AppState::default(),
+            None,
+            &street_query_params,
+            &render_query_params,
+            WasteTypeBitmask::none(),
+        )
+        .await
+        .unwrap_err();
+        status
+    }
+
+    /// Test whether a number of days to predict outside of the bounds is a bad request.
+    ///
+    /// The state has no providers, so a valid request is not found.
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_handle_predict_days() {
+        for (predict_days, status_code) in [
+            (-1, StatusCode::BAD_REQUEST),
+            (i64::MAX, StatusCode::BAD_REQUEST),
+            (365, StatusCode::NOT_FOUND),
+        ] {
+            let render_query_params = RenderQueryParams {
+                predict_days: Some(predict_days),
+                ..RenderQueryParams::default()
+            };
+            assert_eq!(
+                status(render_query_params).await,
+                status_code,
+                "predict_days={predict_days}"
+            );
+        }
+    }
+
+    /// Test whether a horizon outside of the bounds is a bad request.
+    ///
+    /// The state has no providers, so a valid request is not found.
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_handle_horizon_days() {
+        for (horizon_days, status_code) in [
+            (-1, StatusCode::BAD_REQUEST),
+            (i64::MIN, StatusCode::BAD_REQUEST),
+            (731, StatusCode::BAD_REQUEST),
+            (0, StatusCode::NOT_FOUND),
+            (730, StatusCode::NOT_FOUND),
+        ] {
+            let render_query_params = RenderQueryParams {
+                horizon_days: Some(horizon_days),
+                ..RenderQueryParams::default()
+            };
+            assert_eq!(
+                status(render_query_params).await,
+                status_code,
+                "horizon_days={horizon_days}"
+            );
+        }
+    }
 }
@@ -1,10 +1,28 @@
-use axum::{extract::Query, http::StatusCode, response::Response};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Response,
+};
 use kgc_core::garbage_client::WasteTypeBitmask;
 
-use crate::route::calendar::{handle, StreetQueryParams};
+use crate::{
+    route::calendar::{handle, RenderQueryParams, StreetQueryParams},
+    state::AppState,
+};
 
 pub async fn handler(
+    State(state): State<AppState>,
+    provider: Option<Path<String>>,
     Query(street_query_params): Query<StreetQueryParams>,
+    Query(render_query_params): Query<RenderQueryParams>,
 ) -> Result<Response, (StatusCode, String)> {
-    handle(&street_query_params, WasteTypeBitmask::InvertedPaper).await
+    let provider_id = provider.map(|Path(provider)| provider);
+    handle(
+        &state,
+        provider_id.as_deref(),
+        &street_query_params,
+        &render_query_params,
+        WasteTypeBitmask::InvertedPaper,
+    )
+    .await
 }
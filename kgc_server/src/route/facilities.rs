@@ -0,0 +1,45 @@
+pub mod calendar;
+
+use axum::{extract::Query, Json};
+use chrono::Local;
+use kgc_core::facility::{Dataset, Facility, Material};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryParams {
+    street: Option<String>,
+    district: Option<String>,
+    material: Option<Material>,
+    #[serde(default)]
+    open_now: bool,
+}
+
+/// Get the facilities of the bundled dataset which match the query, ordered by their distance to the street and the district.
+fn select<'a>(dataset: &'a Dataset, query_params: &QueryParams) -> Vec<&'a Facility> {
+    let now = Local::now().naive_local();
+    dataset
+        .nearest(
+            query_params.street.as_deref(),
+            query_params.district.as_deref(),
+        )
+        .into_iter()
+        .filter(|facility| {
+            query_params
+                .material
+                .map_or(true, |material| facility.accepts(material))
+        })
+        .filter(|facility| !query_params.open_now || dataset.is_open(facility, now))
+        .collect()
+}
+
+/// Handle facility requests.
+///
+/// All query parameters are optional, e.g. `?district=Durlach&material=green-waste&open_now=true`.
+pub async fn handler(Query(query_params): Query<QueryParams>) -> Json<Vec<Facility>> {
+    let dataset = Dataset::bundled();
+    let facilities = select(&dataset, &query_params)
+        .into_iter()
+        .cloned()
+        .collect();
+    Json(facilities)
+}
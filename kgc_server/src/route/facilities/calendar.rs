@@ -0,0 +1,82 @@
+use axum::{
+    extract::Query,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{Duration, Local};
+use kgc_core::{
+    facility::{get_exceptions_calendar, Dataset},
+    ical::generator::Emitter,
+};
+use serde::Deserialize;
+
+use crate::route::{
+    check_days,
+    facilities::{select, QueryParams},
+    MAX_DAYS,
+};
+
+/// The calendar options, they are extracted separately like the render options of the garbage calendar.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalendarQueryParams {
+    #[serde(default = "default_days")]
+    days: i64,
+}
+
+fn default_days() -> i64 {
+    365
+}
+
+/// Handle requests for the calendar of the days on which the facilities are closed although they are open on that weekday.
+pub async fn handler(
+    Query(query_params): Query<QueryParams>,
+    Query(calendar_query_params): Query<CalendarQueryParams>,
+) -> Result<Response, (StatusCode, String)> {
+    let days = check_days("days", calendar_query_params.days, 1..=MAX_DAYS)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err))?;
+    let dataset = Dataset::bundled();
+    let facilities = select(&dataset, &query_params);
+    let today = Local::now().date_naive();
+    let exceptions = dataset.exceptions(&facilities, today, today + Duration::days(days));
+    let ical_calendar = get_exceptions_calendar(&exceptions);
+    Ok(([(CONTENT_TYPE, "text/calendar")], ical_calendar.generate()).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{extract::Query, http::StatusCode};
+
+    use crate::route::facilities::{
+        calendar::{handler, CalendarQueryParams},
+        QueryParams,
+    };
+
+    /// Test whether a number of days outside of the bounds is a bad request.
+    ///
+    /// This test is offline.
+    #[tokio::test]
+    async fn test_handler_days() {
+        for (days, is_ok) in [
+            (0, false),
+            (-1, false),
+            (i64::MAX, false),
+            (1, true),
+            (730, true),
+        ] {
+            let query_params = QueryParams {
+                street: None,
+                district: None,
+                material: None,
+                open_now: false,
+            };
+            let result = handler(Query(query_params), Query(CalendarQueryParams { days })).await;
+            match result {
+                Ok(_) => assert!(is_ok, "days={days}"),
+                Err((status, _)) => {
+                    assert!(!is_ok, "days={days}");
+                    assert_eq!(status, StatusCode::BAD_REQUEST);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use kgc_core::garbage_client::Notice;
+
+use crate::state::AppState;
+
+/// Handle notice requests.
+///
+/// The notices are the ones currently shown on the official website of the provider, or of the default provider.
+pub async fn handler(
+    State(state): State<AppState>,
+    provider: Option<Path<String>>,
+) -> Result<Json<Vec<Notice>>, (StatusCode, String)> {
+    let provider_id = provider.map(|Path(provider)| provider);
+    let Some(provider_state) = state.provider(provider_id.as_deref()) else {
+        return Err((StatusCode::NOT_FOUND, String::from("unknown provider")));
+    };
+    let notices = provider_state
+        .provider
+        .get_notices()
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    Ok(Json(notices))
+}
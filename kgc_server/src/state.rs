@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, env, path::PathBuf, sync::Arc};
+
+use kgc_core::{
+    archive::Archive,
+    provider::{providers, Provider, DEFAULT_PROVIDER},
+    source::{FallbackSource, SourceConfig, SourceKind},
+};
+
+use crate::Options;
+
+/// The state which is shared by all routes.
+#[derive(Debug, Clone, Default)]
+pub struct AppState {
+    pub providers: Arc<BTreeMap<&'static str, ProviderState>>,
+}
+
+/// The state of a single provider.
+#[derive(Debug, Clone)]
+pub struct ProviderState {
+    pub provider: Arc<dyn Provider>,
+    /// The source chain, which includes the archive if it is enabled.
+    pub source: Arc<FallbackSource>,
+}
+
+impl AppState {
+    /// Create the state from the options and the environment.
+    ///
+    /// The archive is enabled by the archive directory of the options or `KGC_ARCHIVE_DIR` and its look-back window is set by `KGC_LOOK_BACK_DAYS`.
+    /// The default provider archives into the directory itself, every other provider into a subdirectory named after it.
+    /// The sources are tried in the order of `KGC_SOURCES` (`website` by default),
+    /// they are configured by `KGC_HTML_FILE`, `KGC_OFFICIAL_CALENDAR` and `KGC_STATIC_DATASET`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sources are misconfigured, so the server does not start with a broken source chain.
+    pub fn from_env(options: &Options) -> Self {
+        let look_back_days = env::var("KGC_LOOK_BACK_DAYS")
+            .ok()
+            .and_then(|look_back_days| look_back_days.parse().ok())
+            .unwrap_or(365);
+        let archive_directory = options
+            .archive_directory
+            .clone()
+            .or_else(|| env::var_os("KGC_ARCHIVE_DIR").map(PathBuf::from));
+        let source_kinds = env::var("KGC_SOURCES")
+            .unwrap_or_else(|_| String::from("website"))
+            .split(',')
+            .map(|source_kind| source_kind.trim().parse())
+            .collect::<Result<Vec<SourceKind>, String>>()
+            .unwrap_or_else(|err| panic!("{err}"));
+        if archive_directory.is_none() && source_kinds.contains(&SourceKind::Archive) {
+            panic!("the source \"archive\" requires KGC_ARCHIVE_DIR");
+        }
+        let source_config = SourceConfig {
+            html_file: env::var_os("KGC_HTML_FILE").map(PathBuf::from),
+            official_calendar: env::var("KGC_OFFICIAL_CALENDAR").ok(),
+            official_calendar_days: 365,
+            static_dataset: env::var_os("KGC_STATIC_DATASET").map(PathBuf::from),
+            archive: None,
+        };
+        let providers = providers(options.upstream_url.as_deref())
+            .into_iter()
+            .map(|provider| {
+                let archive = archive_directory.as_ref().map(|directory| {
+                    let directory = if provider.id() == DEFAULT_PROVIDER {
+                        directory.clone()
+                    } else {
+                        directory.join(provider.id())
+                    };
+                    Arc::new(Archive::new(directory, look_back_days))
+                });
+                let source = SourceConfig {
+                    archive,
+                    ..source_config.clone()
+                }
+                .chain(provider.clone(), &source_kinds)
+                .unwrap_or_else(|err| panic!("{err}"));
+                let provider_state = ProviderState {
+                    provider: provider.clone(),
+                    source: Arc::new(source),
+                };
+                (provider.id(), provider_state)
+            })
+            .collect();
+        AppState {
+            providers: Arc::new(providers),
+        }
+    }
+
+    /// Get the state of the provider with a specific identifier, or of the default provider.
+    pub fn provider(&self, id: Option<&str>) -> Option<&ProviderState> {
+        self.providers
+            .get(id.unwrap_or(DEFAULT_PROVIDER).to_lowercase().as_str())
+    }
+}